@@ -0,0 +1,78 @@
+use chippy8::{assemble, Interpreter, Quirks};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// The number of instructions executed per benchmark iteration.
+const STEPS: u64 = 10_000;
+
+/// Creates an interpreter for `source` without waiting for the display, so every step executes
+/// an instruction.
+fn interpreter(source: &str) -> Interpreter {
+    let rom = assemble(source).unwrap();
+    let mut interpreter = Interpreter::with_seed(&rom, 0).unwrap();
+    let mut quirks = Quirks::preset("chip8").unwrap();
+    quirks.display_wait = false;
+    interpreter.set_quirks(quirks).unwrap();
+    interpreter
+}
+
+fn bench_steps(c: &mut Criterion, name: &str, source: &str) {
+    let mut interpreter = interpreter(source);
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Elements(STEPS));
+    group.bench_function("steps", |b| {
+        b.iter(|| {
+            for _ in 0..STEPS {
+                interpreter.step().unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn execute(c: &mut Criterion) {
+    bench_steps(
+        c,
+        "execute",
+        "
+        loop:
+            ADD V0, 1
+            ADD V1, V0
+            XOR V2, V1
+            SHR V3, V2
+            SE V0, 0
+            LD I, 0x300
+            JP loop
+        ",
+    );
+}
+
+fn draw(c: &mut Criterion) {
+    bench_steps(
+        c,
+        "draw",
+        "
+            LD V2, 8
+            LD F, V2
+        loop:
+            DRW V0, V1, 5
+            ADD V0, 3
+            ADD V1, 1
+            JP loop
+        ",
+    );
+}
+
+fn clear(c: &mut Criterion) {
+    bench_steps(
+        c,
+        "clear",
+        "
+        loop:
+            CLS
+            JP loop
+        ",
+    );
+}
+
+criterion_group!(benches, execute, draw, clear);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+use zip::{result::ZipError, ZipArchive};
+
+/// Returns true if `path` names a zip archive rather than a ROM.
+pub fn is_zip(path: &str) -> bool {
+    has_extension(path, "zip")
+}
+
+/// Reads the ROM called `entry` from the zip archive at `path`, or the first `.ch8` file in it if
+/// `entry` is `None`.
+pub fn load_rom_from_zip(path: &Path, entry: Option<&str>) -> io::Result<Vec<u8>> {
+    let mut archive = ZipArchive::new(File::open(path)?).map_err(invalid_data)?;
+    let name = match entry {
+        Some(entry) => Ok(entry.to_string()),
+        None => first_rom_name(&mut archive),
+    };
+    let mut file = name
+        .and_then(|name| archive.by_name(&name))
+        .map_err(|e| match e {
+            ZipError::FileNotFound => io::Error::new(
+                io::ErrorKind::NotFound,
+                match entry {
+                    Some(entry) => format!("{} doesn't contain {}", path.display(), entry),
+                    None => format!("{} doesn't contain a .ch8 ROM", path.display()),
+                },
+            ),
+            e => invalid_data(e),
+        })?;
+    eprintln!("Loading {} from the archive...", file.name());
+    let mut rom = Vec::new();
+    file.read_to_end(&mut rom)?;
+    Ok(rom)
+}
+
+/// Returns the name of the first `.ch8` file in the archive, in the order they were added.
+fn first_rom_name(archive: &mut ZipArchive<File>) -> Result<String, ZipError> {
+    for index in 0..archive.len() {
+        let file = archive.by_index_raw(index)?;
+        if file.is_file() && has_extension(file.name(), "ch8") {
+            return Ok(file.name().to_string());
+        }
+    }
+    Err(ZipError::FileNotFound)
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+}
+
+fn invalid_data(error: ZipError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
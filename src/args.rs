@@ -0,0 +1,306 @@
+use crate::{
+    filter::Filter,
+    interpreter::UnknownOpcodePolicy,
+    keymap::KeyMap,
+    palette::{parse_color, Palette},
+    quirks::Quirks,
+};
+use std::{error::Error, str::FromStr};
+
+/// The default size of a CHIP-8 pixel in the window.
+const DEFAULT_SCALE: u32 = 8;
+const DEFAULT_FPS: u32 = 60;
+
+pub const USAGE: &str = "\
+Usage: chippy8 [OPTIONS] <ROM>
+
+Reads the ROM from stdin if <ROM> is -. Options are also read from chippy8.toml in the working
+directory or chippy8/config.toml in the user's config directory, and overridden by the command
+line.
+
+Options:
+  --config <PATH>        Read options from this TOML file instead of the default locations
+  --clock <HZ>           Instructions executed per second
+  --ipf <N>              Instructions executed per frame, instead of --clock, e.g. 11 (660 Hz)
+  --fps <N>              Frames drawn per second [default: 60]
+  --scale <N>            Initial size of a CHIP-8 pixel in the window [default: 8]
+  --fg <RRGGBB>          Color of lit pixels [default: FFFFFF]
+  --bg <RRGGBB>          Color of unlit pixels [default: 000000]
+  --fade                 Fade pixels out over a few frames to reduce flicker
+  --instant-clear        Blank the screen at once when it's cleared, even with --fade
+  --keypad               Show the keypad below the game, clickable with the mouse or by touch
+  --filter <FILTER>      Upscaling filter: nearest, crt or smooth [default: nearest]
+  --mute                 Silence the buzzer
+  --flash                Tint the screen while the buzzer sounds, also when muted
+  --keymap <PRESET>      Key layout: qwerty or arcade [default: qwerty]
+  --quirks <PRESET>      Quirk preset: chip8, schip or xochip [default: detected from the ROM]
+  --i-overflow-sets-vf   Set VF when Fx1E makes I exceed 0x0FFF
+  --vip-hires            Run hires COSMAC VIP programs, which start with 1260, on a 64x64 display
+  --skip-unknown         Skip unknown opcodes instead of halting
+  --byteswap             Swap the bytes of each instruction, for ROMs dumped in the wrong order
+  --entry <ADDRESS>      Load and start the ROM at ADDRESS (hex), e.g. 600 for ETI-660 programs
+  --break <ADDRESS>      Pause before executing the instruction at ADDRESS (hex)
+  --log-code-writes      Print writes by the program to its own code
+  --vip-timing           Make each instruction take as long as on the COSMAC VIP, ignoring --clock
+  --strict               Stop on jumps to odd addresses and sprites read past the end of memory
+  --seed <N>             Seed the random number generator, for reproducible runs
+  --run-frames <N>       Run N frames without a window and exit, failing if the ROM hits an error
+  --dump-display         Print the display as text after --run-frames
+  --screenshot <PATH>    Save the display as a PNG after --run-frames
+  --expect-display <SHA> Fail if the SHA-1 of the display after --run-frames isn't SHA
+  --disasm               Print the disassembled ROM and exit
+  --debug                Run in a text debugger reading commands from stdin instead of a window
+  --help                 Print this help and exit
+";
+
+/// Options only available with the `net` feature, listed after [`USAGE`].
+#[cfg(feature = "net")]
+pub const NET_USAGE: &str = "\
+  --serve <ADDRESS>      Stream the display to spectators connecting to ADDRESS, e.g. 0.0.0.0:8008
+  --watch <ADDRESS>      Watch a game streamed from ADDRESS instead of running a ROM
+";
+
+/// Options only available with the `gamepad` feature, listed after [`USAGE`].
+#[cfg(feature = "gamepad")]
+pub const GAMEPAD_USAGE: &str = "\
+  --button <BUTTON=KEY>  Map a gamepad button to a hex key, e.g. south=5. Buttons: up, down,
+                         left, right, south, east, west, north, lb, rb, lt, rt, select, start
+";
+
+/// Options only available with the `zip` feature, listed after [`USAGE`].
+#[cfg(feature = "zip")]
+pub const ZIP_USAGE: &str = "\
+  --zip-entry <NAME>     Load NAME when <ROM> is a zip archive [default: the first .ch8 file]
+";
+
+/// Command line arguments of the desktop application.
+pub struct Args {
+    pub rom_path: String,
+    pub clock_hz: Option<u32>,
+    pub instructions_per_frame: Option<u32>,
+    pub fps: u32,
+    pub scale: u32,
+    pub palette: Palette,
+    pub fade: bool,
+    pub instant_clear: bool,
+    pub mute: bool,
+    pub flash: bool,
+    pub keymap: KeyMap,
+    pub filter: Filter,
+    pub keypad: bool,
+    /// The quirks to use instead of the detected ones.
+    pub quirks: Option<Quirks>,
+    pub i_overflow_sets_vf: bool,
+    pub vip_hires: bool,
+    pub unknown_opcode_policy: UnknownOpcodePolicy,
+    pub byteswap: bool,
+    pub entry_point: Option<u16>,
+    pub breakpoints: Vec<u16>,
+    pub log_code_writes: bool,
+    pub strict: bool,
+    pub vip_timing: bool,
+    pub seed: Option<u64>,
+    pub run_frames: Option<u32>,
+    pub dump_display: bool,
+    pub screenshot: Option<String>,
+    pub expect_display: Option<String>,
+    pub disasm: bool,
+    pub debug: bool,
+    pub help: bool,
+    /// The address to stream the display from.
+    #[cfg(feature = "net")]
+    pub serve: Option<String>,
+    /// The address of a game to watch instead of running a ROM.
+    #[cfg(feature = "net")]
+    pub watch: Option<String>,
+    /// The file to load from the zip archive at `rom_path`.
+    #[cfg(feature = "zip")]
+    pub zip_entry: Option<String>,
+}
+
+impl Args {
+    /// Parses the arguments, not including the program name.
+    pub fn parse<I>(args: I) -> Result<Args, Box<dyn Error>>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut rom_path = None;
+        let mut parsed = Args::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} requires a value", arg));
+            match arg.as_str() {
+                // Already read by `run`, before the rest of the arguments
+                "--config" => {
+                    value()?;
+                }
+                // The speed given last wins, so the command line overrides the config file
+                "--clock" => {
+                    parsed.clock_hz = Some(parse_positive(&value()?)?);
+                    parsed.instructions_per_frame = None;
+                }
+                "--ipf" => {
+                    parsed.instructions_per_frame = Some(parse_positive(&value()?)?);
+                    parsed.clock_hz = None;
+                }
+                "--fps" => parsed.fps = parse_positive(&value()?)?,
+                "--scale" => parsed.scale = parse_positive(&value()?)?,
+                "--fg" => parsed.palette.fg = parse_color(&value()?)?,
+                "--bg" => parsed.palette.bg = parse_color(&value()?)?,
+                "--fade" => parsed.fade = true,
+                "--instant-clear" => parsed.instant_clear = true,
+                "--mute" => parsed.mute = true,
+                "--flash" => parsed.flash = true,
+                "--keypad" => parsed.keypad = true,
+                "--keymap" => {
+                    let name = value()?;
+                    parsed.keymap = KeyMap::preset(&name).ok_or(format!(
+                        "unknown key map {:?}, expected one of {}",
+                        name,
+                        KeyMap::PRESET_NAMES.join(", ")
+                    ))?;
+                }
+                "--filter" => {
+                    let name = value()?;
+                    parsed.filter = Filter::from_name(&name).ok_or(format!(
+                        "unknown filter {:?}, expected one of {}",
+                        name,
+                        Filter::NAMES.join(", ")
+                    ))?;
+                }
+                "--quirks" => {
+                    let name = value()?;
+                    let quirks = Quirks::preset(&name).ok_or(format!(
+                        "unknown quirk preset {:?}, expected one of {}",
+                        name,
+                        Quirks::PRESET_NAMES.join(", ")
+                    ))?;
+                    parsed.quirks = Some(quirks);
+                }
+                "--i-overflow-sets-vf" => parsed.i_overflow_sets_vf = true,
+                "--vip-hires" => parsed.vip_hires = true,
+                "--skip-unknown" => parsed.unknown_opcode_policy = UnknownOpcodePolicy::Skip,
+                "--byteswap" => parsed.byteswap = true,
+                "--entry" => parsed.entry_point = Some(parse_address(&value()?)?),
+                "--break" => parsed.breakpoints.push(parse_address(&value()?)?),
+                "--log-code-writes" => parsed.log_code_writes = true,
+                "--strict" => parsed.strict = true,
+                "--vip-timing" => parsed.vip_timing = true,
+                "--seed" => {
+                    let seed = value()?;
+                    let seed = seed
+                        .parse()
+                        .map_err(|_| format!("invalid seed {:?}: expected a number", seed))?;
+                    parsed.seed = Some(seed);
+                }
+                "--run-frames" => parsed.run_frames = Some(parse_positive(&value()?)?),
+                "--dump-display" => parsed.dump_display = true,
+                "--screenshot" => parsed.screenshot = Some(value()?),
+                "--expect-display" => parsed.expect_display = Some(value()?),
+                "--disasm" => parsed.disasm = true,
+                "--debug" => parsed.debug = true,
+                "--help" | "-h" => parsed.help = true,
+                #[cfg(feature = "net")]
+                "--serve" => parsed.serve = Some(value()?),
+                #[cfg(feature = "net")]
+                "--watch" => parsed.watch = Some(value()?),
+                #[cfg(feature = "zip")]
+                "--zip-entry" => parsed.zip_entry = Some(value()?),
+                #[cfg(feature = "gamepad")]
+                "--button" => {
+                    let (button, key) = parse_button_mapping(&value()?)?;
+                    parsed.keymap.insert_button(button, key);
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option {:?}, see --help", arg).into())
+                }
+                _ => rom_path = Some(arg),
+            }
+        }
+
+        #[cfg(feature = "net")]
+        let needs_rom = !parsed.help && parsed.watch.is_none();
+        #[cfg(not(feature = "net"))]
+        let needs_rom = !parsed.help;
+        if needs_rom {
+            parsed.rom_path = rom_path.ok_or("Must enter path to a game, see --help")?;
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            rom_path: String::new(),
+            clock_hz: None,
+            instructions_per_frame: None,
+            fps: DEFAULT_FPS,
+            scale: DEFAULT_SCALE,
+            palette: Palette::default(),
+            fade: false,
+            instant_clear: false,
+            mute: false,
+            flash: false,
+            keymap: KeyMap::default(),
+            filter: Filter::default(),
+            keypad: false,
+            quirks: None,
+            i_overflow_sets_vf: false,
+            vip_hires: false,
+            unknown_opcode_policy: UnknownOpcodePolicy::Halt,
+            byteswap: false,
+            entry_point: None,
+            breakpoints: Vec::new(),
+            log_code_writes: false,
+            strict: false,
+            vip_timing: false,
+            seed: None,
+            run_frames: None,
+            dump_display: false,
+            screenshot: None,
+            expect_display: None,
+            disasm: false,
+            debug: false,
+            help: false,
+            #[cfg(feature = "net")]
+            serve: None,
+            #[cfg(feature = "net")]
+            watch: None,
+            #[cfg(feature = "zip")]
+            zip_entry: None,
+        }
+    }
+}
+
+/// Parses a number larger than zero.
+fn parse_positive<T>(s: &str) -> Result<T, Box<dyn Error>>
+where
+    T: FromStr + Default + PartialOrd,
+{
+    match s.parse() {
+        Ok(n) if n > T::default() => Ok(n),
+        _ => Err(format!("invalid value {:?}: expected a positive number", s).into()),
+    }
+}
+
+/// Parses a hexadecimal address, with or without a `0x` prefix.
+fn parse_address(s: &str) -> Result<u16, Box<dyn Error>> {
+    let digits = s.trim_start_matches("0x");
+    Ok(u16::from_str_radix(digits, 16).map_err(|e| format!("invalid address {:?}: {}", s, e))?)
+}
+
+/// Parses a `BUTTON=KEY` gamepad mapping, where `KEY` is a single hex digit.
+#[cfg(feature = "gamepad")]
+fn parse_button_mapping(s: &str) -> Result<(gilrs::Button, crate::Key), Box<dyn Error>> {
+    let invalid = || format!("invalid button mapping {:?}: expected BUTTON=KEY", s);
+    let (name, key) = s.split_once('=').ok_or_else(invalid)?;
+    let button = KeyMap::parse_button(name).ok_or_else(|| format!("unknown button {:?}", name))?;
+    let key = u8::from_str_radix(key, 16)
+        .ok()
+        .and_then(crate::Key::from_hex)
+        .ok_or_else(invalid)?;
+    Ok((button, key))
+}
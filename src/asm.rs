@@ -0,0 +1,314 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The address the first assembled byte is loaded at.
+const START_ADDRESS: usize = 0x200;
+
+const MNEMONICS: &[&str] = &[
+    "ADD", "AND", "CALL", "CLS", "DB", "DRW", "JP", "LD", "LOAD", "OR", "PLANE", "RET", "RND",
+    "SAVE", "SE", "SHL", "SHR", "SKNP", "SKP", "SNE", "SUB", "SUBN", "SYS", "XOR",
+];
+
+/// An error in the assembly source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    /// The line the error is on, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+/// An instruction operand.
+enum Operand {
+    V(u8),
+    I,
+    /// The memory at I, written `[I]`.
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    B,
+    /// A 16-bit address for XO-CHIP's `F000 nnnn`, written `LONG nnnn`. Without an address,
+    /// only the `F000` half is assembled.
+    Long(Option<Value>),
+    Value(Value),
+}
+
+/// A number, or a label that resolves to an address.
+enum Value {
+    Number(u32),
+    Label(String),
+}
+
+struct Statement {
+    mnemonic: String,
+    operands: Vec<Operand>,
+}
+
+impl Statement {
+    /// Returns the number of bytes the statement assembles to.
+    fn size(&self) -> usize {
+        match (self.mnemonic.as_str(), self.operands.as_slice()) {
+            ("DB", operands) => operands.len(),
+            (_, [_, Operand::Long(Some(_))]) => 4,
+            _ => 2,
+        }
+    }
+}
+
+/// Assembles CHIP-8 source into a ROM to be loaded at 0x200.
+///
+/// The source uses the same mnemonics as [`disassemble`](crate::disassemble), one instruction
+/// per line, e.g. `LD V0, 0x0A` or `DRW V0, V1, 5`. Registers are written `V0` through `VF`, or
+/// `V10` through `V15`. Numbers are decimal, or hexadecimal and binary with a `0x` or `0b`
+/// prefix.
+///
+/// - `name:` defines a label at the next instruction, which can be used in place of an address,
+///   e.g. `JP name`.
+/// - `DB 0xF0, 0x90` inserts raw bytes, e.g. for sprite data.
+/// - `;` starts a comment.
+/// - Anything up to `->` is ignored, so lines from [`disassemble`](crate::disassemble) assemble
+///   back to the same bytes.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut address = START_ADDRESS;
+
+    // First pass: parse the statements and find the address of every label
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message| AsmError {
+            line: line_number,
+            message,
+        };
+
+        let mut rest = strip(line);
+        while let Some((label, after)) = split_label(rest) {
+            if !is_identifier(label) {
+                return Err(error(format!("invalid label {:?}", label)));
+            }
+            if labels.insert(label, address).is_some() {
+                return Err(error(format!("label {:?} is already defined", label)));
+            }
+            rest = after.trim();
+        }
+
+        if rest.is_empty() {
+            continue;
+        }
+        let statement = parse_statement(rest).map_err(error)?;
+        address += statement.size();
+        statements.push((line_number, statement));
+    }
+
+    // Second pass: encode the statements now that all labels are known
+    let mut rom = Vec::new();
+    for (line_number, statement) in statements {
+        encode(&statement, &labels, &mut rom).map_err(|message| AsmError {
+            line: line_number,
+            message,
+        })?;
+    }
+
+    Ok(rom)
+}
+
+/// Removes comments, disassembler byte columns and surrounding whitespace from a line.
+fn strip(line: &str) -> &str {
+    let line = line.split(';').next().unwrap_or_default();
+    let line = match line.split_once("->") {
+        Some((_, instruction)) => instruction,
+        None => line,
+    };
+    line.trim()
+}
+
+/// Splits `name: rest` into the label name and the rest of the line.
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (label, rest) = line.split_once(':')?;
+    let label = label.trim();
+    if label.contains(char::is_whitespace) {
+        return None;
+    }
+    Some((label, rest))
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_statement(line: &str) -> Result<Statement, String> {
+    let (mnemonic, operands) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let operands = if operands.trim().is_empty() {
+        Vec::new()
+    } else {
+        operands
+            .split(',')
+            .map(|operand| parse_operand(operand.trim()))
+            .collect::<Result<_, _>>()?
+    };
+
+    Ok(Statement {
+        mnemonic: mnemonic.to_ascii_uppercase(),
+        operands,
+    })
+}
+
+fn parse_operand(s: &str) -> Result<Operand, String> {
+    let upper = s.to_ascii_uppercase();
+    let operand = match upper.as_str() {
+        "I" => Operand::I,
+        "[I]" => Operand::IndirectI,
+        "DT" => Operand::Dt,
+        "ST" => Operand::St,
+        "K" => Operand::K,
+        "F" => Operand::F,
+        "B" => Operand::B,
+        "LONG" => Operand::Long(None),
+        _ if upper.starts_with("LONG ") => match parse_operand(s[4..].trim())? {
+            Operand::Value(value) => Operand::Long(Some(value)),
+            _ => return Err(format!("invalid operand {:?}", s)),
+        },
+        _ => {
+            if let Some(x) = parse_register(&upper) {
+                Operand::V(x)
+            } else if let Some(n) = parse_number(&upper) {
+                Operand::Value(Value::Number(n))
+            } else if is_identifier(s) {
+                Operand::Value(Value::Label(s.to_string()))
+            } else {
+                return Err(format!("invalid operand {:?}", s));
+            }
+        }
+    };
+    Ok(operand)
+}
+
+/// Parses `V0` through `VF`, as well as `V10` through `V15` as written by the disassembler.
+fn parse_register(s: &str) -> Option<u8> {
+    let index = s.strip_prefix('V')?;
+    match index.parse() {
+        Ok(x) if x < 16 => Some(x),
+        _ if index.len() == 1 => u8::from_str_radix(index, 16).ok(),
+        _ => None,
+    }
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    if let Some(hex) = s.strip_prefix("0X") {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = s.strip_prefix("0B") {
+        u32::from_str_radix(binary, 2).ok()
+    } else if s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Returns the value of a number or label, if it is at most `max`.
+fn resolve(value: &Value, labels: &HashMap<&str, usize>, max: u32) -> Result<u16, String> {
+    let n = match value {
+        Value::Number(n) => *n,
+        Value::Label(label) => *labels
+            .get(label.as_str())
+            .ok_or(format!("undefined label {:?}", label))? as u32,
+    };
+    if n > max {
+        return Err(format!("value 0x{:X} is larger than 0x{:X}", n, max));
+    }
+    Ok(n as u16)
+}
+
+/// Encodes a statement and appends the bytes to `rom`.
+fn encode(
+    statement: &Statement,
+    labels: &HashMap<&str, usize>,
+    rom: &mut Vec<u8>,
+) -> Result<(), String> {
+    use Operand::*;
+
+    let addr = |value| resolve(value, labels, 0xFFF);
+    let byte = |value| resolve(value, labels, 0xFF);
+    let nibble = |value| resolve(value, labels, 0xF);
+    let x = |x: &u8| (*x as u16) << 8;
+    let xy = |x: &u8, y: &u8| (*x as u16) << 8 | (*y as u16) << 4;
+
+    let opcode = match (statement.mnemonic.as_str(), statement.operands.as_slice()) {
+        ("DB", operands) => {
+            for operand in operands {
+                match operand {
+                    Value(value) => rom.push(byte(value)? as u8),
+                    _ => return Err("DB only takes numbers".to_string()),
+                }
+            }
+            return Ok(());
+        }
+        ("LD", [I, Long(Some(a))]) => {
+            rom.extend_from_slice(&[0xF0, 0x00]);
+            rom.extend_from_slice(&resolve(a, labels, 0xFFFF)?.to_be_bytes());
+            return Ok(());
+        }
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SYS", [Value(a)]) => addr(a)?,
+        ("JP", [Value(a)]) => 0x1000 | addr(a)?,
+        ("CALL", [Value(a)]) => 0x2000 | addr(a)?,
+        ("SE", [V(vx), Value(k)]) => 0x3000 | x(vx) | byte(k)?,
+        ("SNE", [V(vx), Value(k)]) => 0x4000 | x(vx) | byte(k)?,
+        ("SE", [V(vx), V(vy)]) => 0x5000 | xy(vx, vy),
+        ("SAVE", [V(vx), V(vy)]) => 0x5002 | xy(vx, vy),
+        ("LOAD", [V(vx), V(vy)]) => 0x5003 | xy(vx, vy),
+        ("LD", [V(vx), Value(k)]) => 0x6000 | x(vx) | byte(k)?,
+        ("ADD", [V(vx), Value(k)]) => 0x7000 | x(vx) | byte(k)?,
+        ("LD", [V(vx), V(vy)]) => 0x8000 | xy(vx, vy),
+        ("OR", [V(vx), V(vy)]) => 0x8001 | xy(vx, vy),
+        ("AND", [V(vx), V(vy)]) => 0x8002 | xy(vx, vy),
+        ("XOR", [V(vx), V(vy)]) => 0x8003 | xy(vx, vy),
+        ("ADD", [V(vx), V(vy)]) => 0x8004 | xy(vx, vy),
+        ("SUB", [V(vx), V(vy)]) => 0x8005 | xy(vx, vy),
+        ("SHR", [V(vx)]) => 0x8006 | xy(vx, vx),
+        ("SHR", [V(vx), V(vy)]) => 0x8006 | xy(vx, vy),
+        ("SUBN", [V(vx), V(vy)]) => 0x8007 | xy(vx, vy),
+        ("SHL", [V(vx)]) => 0x800E | xy(vx, vx),
+        ("SHL", [V(vx), V(vy)]) => 0x800E | xy(vx, vy),
+        ("SNE", [V(vx), V(vy)]) => 0x9000 | xy(vx, vy),
+        ("LD", [I, Value(a)]) => 0xA000 | addr(a)?,
+        ("LD", [I, Long(None)]) => 0xF000,
+        ("JP", [V(0), Value(a)]) => 0xB000 | addr(a)?,
+        ("RND", [V(vx), Value(k)]) => 0xC000 | x(vx) | byte(k)?,
+        ("DRW", [V(vx), V(vy), Value(n)]) => 0xD000 | xy(vx, vy) | nibble(n)?,
+        ("SKP", [V(vx)]) => 0xE09E | x(vx),
+        ("SKNP", [V(vx)]) => 0xE0A1 | x(vx),
+        ("PLANE", [Value(n)]) => 0xF001 | nibble(n)? << 8,
+        ("LD", [V(vx), Dt]) => 0xF007 | x(vx),
+        ("LD", [V(vx), K]) => 0xF00A | x(vx),
+        ("LD", [Dt, V(vx)]) => 0xF015 | x(vx),
+        ("LD", [St, V(vx)]) => 0xF018 | x(vx),
+        ("ADD", [I, V(vx)]) => 0xF01E | x(vx),
+        ("LD", [F, V(vx)]) => 0xF029 | x(vx),
+        ("LD", [B, V(vx)]) => 0xF033 | x(vx),
+        ("LD", [IndirectI, V(vx)]) => 0xF055 | x(vx),
+        ("LD", [V(vx), IndirectI]) => 0xF065 | x(vx),
+        (mnemonic, _) if MNEMONICS.contains(&mnemonic) => {
+            return Err(format!("invalid operands for {}", mnemonic))
+        }
+        (mnemonic, _) => return Err(format!("unknown mnemonic {:?}", mnemonic)),
+    };
+
+    rom.extend_from_slice(&opcode.to_be_bytes());
+    Ok(())
+}
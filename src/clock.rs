@@ -0,0 +1,35 @@
+use std::time::Duration;
+use web_time::Instant;
+
+/// A source of time for the interpreter's clock and timers.
+///
+/// Only differences between readings matter, so a clock can start counting from any point.
+pub trait Clock {
+    /// Returns the time passed since some fixed starting point.
+    fn now(&self) -> Duration;
+}
+
+/// The system's monotonic clock, using `performance.now()` on the web.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
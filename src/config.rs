@@ -0,0 +1,196 @@
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+/// The name of the config file looked for in the working directory.
+const LOCAL_FILE_NAME: &str = "chippy8.toml";
+
+/// Settings read from a TOML config file, each mirroring the command line option of the same
+/// name.
+///
+/// Settings for a single game go in a `[games.<KEY>]` section, where the key is the SHA-1 hash of
+/// the ROM or its file name, with or without the extension. They're applied on top of the rest.
+///
+/// ```toml
+/// clock = 700
+/// scale = 10
+/// fg = "FFB000"
+/// bg = "1A1200"
+/// keymap = "arcade"
+///
+/// [games.PONG]
+/// clock = 500
+/// quirks = "chip8"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub clock: Option<u32>,
+    pub ipf: Option<u32>,
+    pub fps: Option<u32>,
+    pub scale: Option<u32>,
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub fade: Option<bool>,
+    #[serde(rename = "instant-clear")]
+    pub instant_clear: Option<bool>,
+    pub mute: Option<bool>,
+    pub flash: Option<bool>,
+    pub keypad: Option<bool>,
+    pub keymap: Option<String>,
+    pub quirks: Option<String>,
+    pub filter: Option<String>,
+    pub games: HashMap<String, Config>,
+}
+
+impl Config {
+    /// Reads the config file at `path`, or the first one found at the default locations if
+    /// `path` is `None`: `chippy8.toml` in the working directory, then `chippy8/config.toml` in
+    /// the user's config directory.
+    ///
+    /// Returns an empty config if there is no file at the default locations.
+    pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn Error>> {
+        if let Some(path) = path {
+            return Self::read(path);
+        }
+
+        for path in default_paths() {
+            match fs::read_to_string(&path) {
+                Ok(text) => return Self::parse(&text, &path),
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("could not read {}: {}", path.display(), e).into()),
+            }
+        }
+        Ok(Config::default())
+    }
+
+    fn read(path: &Path) -> Result<Config, Box<dyn Error>> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+        Self::parse(&text, path)
+    }
+
+    fn parse(text: &str, path: &Path) -> Result<Config, Box<dyn Error>> {
+        let config: Config = toml::from_str(text)
+            .map_err(|e| format!("invalid config {}: {}", path.display(), e))?;
+        if let Some(key) = config
+            .games
+            .iter()
+            .find_map(|(key, game)| (!game.games.is_empty()).then_some(key))
+        {
+            return Err(format!(
+                "invalid config {}: games.{} can't have games of its own",
+                path.display(),
+                key
+            )
+            .into());
+        }
+        Ok(config)
+    }
+
+    /// Returns the settings for the game `rom` loaded from `rom_path` as command line arguments,
+    /// i.e. the settings of its `[games]` section, if any, in place of the rest.
+    pub fn args_for_rom(&self, rom_path: &str, rom: &[u8]) -> Vec<String> {
+        match self.game(rom_path, rom) {
+            Some(game) => self.merged(game).to_args(),
+            None => self.to_args(),
+        }
+    }
+
+    /// Returns the settings of `game` where it has them, and the rest of these otherwise.
+    ///
+    /// Merged before converting to arguments, since a setting like `mute = false` has no
+    /// argument that turns it back off.
+    fn merged(&self, game: &Config) -> Config {
+        // `clock` and `ipf` both set the speed, so a game setting either replaces both
+        let speed = if game.clock.is_some() || game.ipf.is_some() {
+            game
+        } else {
+            self
+        };
+        Config {
+            clock: speed.clock,
+            ipf: speed.ipf,
+            fps: game.fps.or(self.fps),
+            scale: game.scale.or(self.scale),
+            fg: game.fg.clone().or_else(|| self.fg.clone()),
+            bg: game.bg.clone().or_else(|| self.bg.clone()),
+            fade: game.fade.or(self.fade),
+            instant_clear: game.instant_clear.or(self.instant_clear),
+            mute: game.mute.or(self.mute),
+            flash: game.flash.or(self.flash),
+            keypad: game.keypad.or(self.keypad),
+            keymap: game.keymap.clone().or_else(|| self.keymap.clone()),
+            quirks: game.quirks.clone().or_else(|| self.quirks.clone()),
+            filter: game.filter.clone().or_else(|| self.filter.clone()),
+            games: HashMap::new(),
+        }
+    }
+
+    fn game(&self, rom_path: &str, rom: &[u8]) -> Option<&Config> {
+        let hash = sha1_smol::Sha1::from(rom).digest().to_string();
+        let path = Path::new(rom_path);
+        let names = [path.file_name(), path.file_stem()];
+        self.games.iter().find_map(|(key, game)| {
+            let matches = key.eq_ignore_ascii_case(&hash)
+                || names.iter().flatten().any(|name| *name == key.as_str());
+            matches.then_some(game)
+        })
+    }
+
+    /// Returns the settings as command line arguments, so they can be parsed before the actual
+    /// arguments and be overridden by them.
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut option = |name: &str, value: Option<String>| {
+            if let Some(value) = value {
+                args.push(format!("--{}", name));
+                args.push(value);
+            }
+        };
+        option("clock", self.clock.map(|hz| hz.to_string()));
+        option("ipf", self.ipf.map(|ipf| ipf.to_string()));
+        option("fps", self.fps.map(|fps| fps.to_string()));
+        option("scale", self.scale.map(|scale| scale.to_string()));
+        option("fg", self.fg.clone());
+        option("bg", self.bg.clone());
+        option("keymap", self.keymap.clone());
+        option("quirks", self.quirks.clone());
+        option("filter", self.filter.clone());
+
+        if self.fade == Some(true) {
+            args.push("--fade".to_string());
+        }
+        if self.instant_clear == Some(true) {
+            args.push("--instant-clear".to_string());
+        }
+        if self.mute == Some(true) {
+            args.push("--mute".to_string());
+        }
+        if self.flash == Some(true) {
+            args.push("--flash".to_string());
+        }
+        if self.keypad == Some(true) {
+            args.push("--keypad".to_string());
+        }
+        args
+    }
+}
+
+fn default_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(LOCAL_FILE_NAME)];
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    if let Some(dir) = config_dir {
+        paths.push(dir.join("chippy8").join("config.toml"));
+    }
+    paths
+}
@@ -0,0 +1,208 @@
+use crate::{disassemble, Chip8Error, Interpreter};
+use std::io::{self, BufRead, Write};
+
+/// How many steps `z` can undo.
+const STEP_HISTORY: usize = 64;
+
+/// The most frames `c` runs before giving control back, in case no breakpoint is hit.
+const MAX_CONTINUE_FRAMES: u32 = 60 * 60;
+
+const HELP: &str = "\
+Commands:
+  s [N]             Step N instructions [default: 1]
+  z [N]             Undo the last N steps [default: 1]
+  c                 Continue until a breakpoint, for at most a minute of emulated time
+  b <ADDRESS>       Add a breakpoint (hex)
+  u <ADDRESS>       Remove a breakpoint (hex)
+  r                 Print the registers, timers and stack
+  m <ADDRESS> [N]   Dump N bytes of memory (hex address) [default: 16]
+  w <ADDRESS> <XX>  Write the byte XX to memory (hex)
+  d                 Disassemble around the program counter
+  p                 Print the display
+  h                 Print this help
+  q                 Quit
+";
+
+/// Runs a text debugger for `interpreter`, reading commands from stdin until `q` or the end of
+/// input.
+pub fn run_debugger(mut interpreter: Interpreter) -> Result<(), Chip8Error> {
+    interpreter.set_step_history(STEP_HISTORY);
+    print!("{}", HELP);
+    let stdin = io::stdin();
+    loop {
+        print!("{:03X}> ", interpreter.pc());
+        io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => continue,
+        };
+        let args: Vec<&str> = words.collect();
+
+        let result = match command {
+            "s" => step(&mut interpreter, &args),
+            "z" => step_back(&mut interpreter, &args),
+            "c" => continue_running(&mut interpreter),
+            "b" => parse_address(&args, 0).map(|address| interpreter.add_breakpoint(address)),
+            "u" => parse_address(&args, 0).map(|address| interpreter.remove_breakpoint(address)),
+            "r" => {
+                print_registers(&interpreter);
+                Ok(())
+            }
+            "m" => dump_memory(&interpreter, &args),
+            "w" => write_memory(&mut interpreter, &args),
+            "d" => {
+                print_disassembly(&interpreter);
+                Ok(())
+            }
+            "p" => {
+                print_display(&interpreter);
+                Ok(())
+            }
+            "h" => {
+                print!("{}", HELP);
+                Ok(())
+            }
+            "q" => return Ok(()),
+            _ => Err(format!("unknown command {:?}, see h", command)),
+        };
+        if let Err(e) = result {
+            println!("Error: {}", e);
+        }
+    }
+}
+
+fn step(interpreter: &mut Interpreter, args: &[&str]) -> Result<(), String> {
+    let count = parse_count(args)?;
+    for _ in 0..count {
+        let pc = interpreter.pc();
+        interpreter.step().map_err(|e| e.to_string())?;
+        println!("{:03X}: {:04X}", pc, interpreter.opcode_at(pc));
+    }
+    Ok(())
+}
+
+fn step_back(interpreter: &mut Interpreter, args: &[&str]) -> Result<(), String> {
+    let count = parse_count(args)?;
+    for _ in 0..count {
+        if !interpreter.step_back() {
+            return Err(format!(
+                "no more steps to undo, at most {} are kept",
+                STEP_HISTORY
+            ));
+        }
+    }
+    println!("Back at {:03X}", interpreter.pc());
+    Ok(())
+}
+
+fn continue_running(interpreter: &mut Interpreter) -> Result<(), String> {
+    // Steps past a breakpoint at the current instruction instead of stopping on it again
+    interpreter.step().map_err(|e| e.to_string())?;
+    for _ in 0..MAX_CONTINUE_FRAMES {
+        interpreter.step_frame().map_err(|e| e.to_string())?;
+        if let Some(address) = interpreter.hit_breakpoint() {
+            println!("Breakpoint hit at {:03X}", address);
+            return Ok(());
+        }
+        if interpreter.is_halted() {
+            println!("Halted at {:03X}", interpreter.pc());
+            return Ok(());
+        }
+    }
+    println!("No breakpoint hit after {} frames", MAX_CONTINUE_FRAMES);
+    Ok(())
+}
+
+fn print_registers(interpreter: &Interpreter) {
+    println!(
+        "PC: {:03X}  I: {:03X}",
+        interpreter.pc(),
+        interpreter.reg_i()
+    );
+    for (row, registers) in interpreter.registers().chunks(8).enumerate() {
+        let values: Vec<String> = registers
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("V{:X}: {:02X}", row * 8 + i, value))
+            .collect();
+        println!("{}", values.join("  "));
+    }
+    println!(
+        "DT: {:02X}  ST: {:02X}",
+        interpreter.delay_timer(),
+        interpreter.sound_timer()
+    );
+    let frames: Vec<String> = interpreter
+        .stack_frames()
+        .iter()
+        .map(|address| format!("{:03X}", address))
+        .collect();
+    println!("Stack: [{}]", frames.join(", "));
+}
+
+fn dump_memory(interpreter: &Interpreter, args: &[&str]) -> Result<(), String> {
+    let start = parse_address(args, 0)? as usize;
+    let end = match args.get(1) {
+        Some(len) => len
+            .parse()
+            .ok()
+            .and_then(|len| start.checked_add(len))
+            .ok_or_else(|| format!("invalid length {:?}", len))?,
+        None => start + 16,
+    };
+    let bytes = interpreter
+        .memory_slice(start..end)
+        .map_err(|e| e.to_string())?;
+    for (row, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02X}", byte)).collect();
+        println!("{:03X}: {}", start + row * 16, hex.join(" "));
+    }
+    Ok(())
+}
+
+fn write_memory(interpreter: &mut Interpreter, args: &[&str]) -> Result<(), String> {
+    let address = parse_address(args, 0)?;
+    let value = args.get(1).ok_or("missing value")?;
+    let value = u8::from_str_radix(value.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("invalid value {:?}", value))?;
+    interpreter.poke(address, value).map_err(|e| e.to_string())
+}
+
+fn print_disassembly(interpreter: &Interpreter) {
+    let pc = interpreter.pc();
+    let start = pc.saturating_sub(8);
+    let end = (pc + 10).min(interpreter.memory_size());
+    let bytes = interpreter.memory_slice(start..end).unwrap_or_default();
+    // The disassembler numbers the bytes from 0x200
+    for (address, line) in disassemble(bytes) {
+        let address = start + address as usize - 0x200;
+        let marker = if address == pc { ">" } else { " " };
+        println!("{} {:03X}: {}", marker, address, line);
+    }
+}
+
+fn print_display(interpreter: &Interpreter) {
+    print!("{}", interpreter.display().to_half_blocks());
+}
+
+/// Parses the optional count of `s` and `z`.
+fn parse_count(args: &[&str]) -> Result<u32, String> {
+    match args.first() {
+        Some(count) => count
+            .parse()
+            .map_err(|_| format!("invalid count {:?}", count)),
+        None => Ok(1),
+    }
+}
+
+fn parse_address(args: &[&str], index: usize) -> Result<u16, String> {
+    let arg = args.get(index).ok_or("missing address")?;
+    let digits = arg.trim_start_matches("0x");
+    u16::from_str_radix(digits, 16).map_err(|_| format!("invalid address {:?}", arg))
+}
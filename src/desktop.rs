@@ -0,0 +1,720 @@
+use crate::args::{Args, USAGE};
+use crate::config::Config;
+use crate::filter::Filter;
+use crate::keypad::{draw_keypad, key_at, KEYPAD_HEIGHT};
+use crate::overlay::draw_overlay;
+use crate::screenshot::save_screenshot;
+use crate::{
+    byte_swap, disassemble, looks_byte_swapped, Chip8Error, DisplayBuffer, FadeBuffer, Interpreter,
+    Key, Palette, Timing, UnknownOpcodePolicy,
+};
+use pixels::{Pixels, SurfaceTexture};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use winit::event_loop::{self, EventLoop};
+use winit::{
+    dpi::{LogicalSize, PhysicalPosition},
+    event::{self, Event},
+    window::{Fullscreen, Window, WindowBuilder},
+};
+
+/// How many times faster the emulator runs while Tab is held.
+const TURBO_SPEED: f32 = 5.0;
+/// How fast the emulator runs in slow motion.
+const SLOW_MOTION_SPEED: f32 = 0.1;
+/// The color the screen is tinted towards while the buzzer sounds with `--flash`.
+const FLASH_COLOR: [u8; 4] = [0xFF, 0x40, 0x40, 0xFF];
+/// How much of [`FLASH_COLOR`] is mixed into the screen, out of 255.
+const FLASH_STRENGTH: u16 = 96;
+/// How many frames can be rewound while paused, 10 seconds at 60 Hz.
+const REWIND_FRAMES: usize = 600;
+
+/// Runs the emulator as a desktop application, loading the ROM given on the command line.
+pub fn run() -> Result<(), Chip8Error> {
+    let cli_args: Vec<String> = std::env::args().skip(1).collect();
+    let config_path = cli_args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| cli_args.get(i + 1))
+        .map(Path::new);
+    let config = Config::load(config_path).map_err(config_error)?;
+    let args = Args::parse(config.to_args().into_iter().chain(cli_args.iter().cloned()))
+        .map_err(config_error)?;
+    if args.help {
+        print!("{}", USAGE);
+        #[cfg(feature = "net")]
+        print!("{}", crate::args::NET_USAGE);
+        #[cfg(feature = "gamepad")]
+        print!("{}", crate::args::GAMEPAD_USAGE);
+        #[cfg(feature = "zip")]
+        print!("{}", crate::args::ZIP_USAGE);
+        return Ok(());
+    }
+
+    #[cfg(feature = "net")]
+    if let Some(address) = &args.watch {
+        return run_spectator(address, &args);
+    }
+
+    #[cfg(feature = "zip")]
+    let zip_entry = args.zip_entry.as_deref();
+    #[cfg(not(feature = "zip"))]
+    let zip_entry = None;
+    let game_data = read_rom(&args.rom_path, zip_entry)?;
+    // Parse again now that the ROM is known, to apply its section of the config
+    let args = Args::parse(
+        config
+            .args_for_rom(&args.rom_path, &game_data)
+            .into_iter()
+            .chain(cli_args.iter().cloned()),
+    )
+    .map_err(config_error)?;
+    let game_data = if args.byteswap {
+        byte_swap(&game_data)
+    } else {
+        if looks_byte_swapped(&game_data) {
+            eprintln!("Warning: the ROM looks byte-swapped, try --byteswap if it doesn't run");
+        }
+        game_data
+    };
+
+    if args.disasm {
+        for (address, line) in disassemble(&game_data) {
+            println!("{:03X}: {}", address, line);
+        }
+        return Ok(());
+    }
+
+    if args.debug {
+        let interpreter = create_interpreter(&args, &game_data)?;
+        return crate::debugger::run_debugger(interpreter);
+    }
+
+    if let Some(frames) = args.run_frames {
+        let interpreter = create_interpreter(&args, &game_data)?;
+        return crate::headless::run_headless(interpreter, frames, &args);
+    }
+
+    run_with_args(args, &game_data)
+}
+
+/// Runs the emulator as a desktop application with `rom` and the default options.
+pub fn run_rom(rom: &[u8]) -> Result<(), Chip8Error> {
+    run_with_args(Args::default(), rom)
+}
+
+/// Reads the ROM at `path`, relative to the current directory, or from stdin if `path` is `-`.
+///
+/// With the `zip` feature, the first `.ch8` file is read if `path` is a zip archive.
+pub fn load_rom_bytes(path: &str) -> io::Result<Vec<u8>> {
+    read_rom(path, None)
+}
+
+/// Reads the ROM like [`load_rom_bytes`], but reads the file `zip_entry` from a zip archive if
+/// one is given.
+///
+/// Progress goes to stderr, so it doesn't mix with the output of `--disasm` or `--run-frames`.
+#[cfg_attr(not(feature = "zip"), allow(unused_variables))]
+fn read_rom(path: &str, zip_entry: Option<&str>) -> io::Result<Vec<u8>> {
+    let mut game_data = Vec::new();
+    #[cfg(feature = "zip")]
+    if crate::archive::is_zip(path) {
+        let game_path = std::env::current_dir()?.join(path);
+        eprintln!("Loading archive {:?}...", game_path);
+        return crate::load_rom_from_zip(&game_path, zip_entry);
+    }
+    if path == "-" {
+        eprintln!("Loading game from stdin...");
+        io::stdin().read_to_end(&mut game_data)?;
+    } else {
+        let game_path = std::env::current_dir()?.join(path);
+        eprintln!("Loading game from {:?}...", game_path);
+        File::open(game_path)?.read_to_end(&mut game_data)?;
+    }
+    Ok(game_data)
+}
+
+/// Creates an interpreter for `game_data`, configured as the arguments say.
+fn create_interpreter(args: &Args, game_data: &[u8]) -> Result<Interpreter, Chip8Error> {
+    let mut interpreter = match args.entry_point {
+        Some(entry_point) => Interpreter::with_entry_point(game_data, entry_point)?,
+        None => Interpreter::try_new(game_data)?,
+    };
+    interpreter.set_unknown_opcode_policy(args.unknown_opcode_policy);
+    if args.unknown_opcode_policy == UnknownOpcodePolicy::Skip {
+        interpreter.set_skipped_opcode_handler(Some(Box::new(|opcode| {
+            eprintln!("skipping unknown opcode: {:04X}", opcode)
+        })));
+    }
+    interpreter.set_muted(args.mute);
+    interpreter.set_strict(args.strict);
+    if let Some(seed) = args.seed {
+        interpreter.set_seed(seed);
+    }
+    if args.vip_timing {
+        interpreter.set_timing(Timing::Vip);
+    }
+    if let Some(quirks) = args.quirks {
+        interpreter.set_quirks(quirks)?;
+    }
+    if args.i_overflow_sets_vf {
+        let mut quirks = interpreter.quirks();
+        quirks.i_overflow_sets_vf = true;
+        interpreter.set_quirks(quirks)?;
+    }
+    if args.vip_hires {
+        let mut quirks = interpreter.quirks();
+        quirks.vip_hires = true;
+        interpreter.set_quirks(quirks)?;
+    }
+    if let Some(ipf) = args.instructions_per_frame {
+        interpreter.set_instructions_per_frame(ipf);
+    }
+    if let Some(clock_hz) = args.clock_hz {
+        interpreter.set_clock_hz(clock_hz);
+    }
+    for &address in &args.breakpoints {
+        interpreter.add_breakpoint(address);
+    }
+    if args.log_code_writes {
+        interpreter.set_self_modifying_write_handler(Some(Box::new(|write| {
+            println!(
+                "{:03X}: wrote {:02X} to code at {:03X}",
+                write.pc, write.value, write.target
+            )
+        })));
+    }
+    Ok(interpreter)
+}
+
+/// Wraps an error from parsing the arguments or config file.
+fn config_error(e: Box<dyn std::error::Error>) -> Chip8Error {
+    Chip8Error::Config(e.to_string())
+}
+
+fn run_with_args(args: Args, game_data: &[u8]) -> Result<(), Chip8Error> {
+    let mut interpreter = create_interpreter(&args, game_data)?;
+    interpreter.set_rewind_history(REWIND_FRAMES);
+    let Args {
+        fps,
+        scale,
+        palette,
+        mut fade,
+        mut flash,
+        instant_clear,
+        keymap,
+        filter,
+        keypad,
+        #[cfg(feature = "net")]
+        serve,
+        ..
+    } = args;
+
+    let mut display_height = interpreter.display().height();
+    let height = frame_height(display_height, keypad);
+    let event_loop = EventLoop::new();
+    let (window, mut pixels) = create_window(&event_loop, "CHIP 8", scale, height, filter)?;
+    let mut frame = vec![0; DisplayBuffer::WIDTH * height * 4];
+
+    #[cfg(feature = "net")]
+    let mut server = match serve {
+        Some(address) => {
+            let server = crate::net::SpectatorServer::bind(address)?;
+            println!("Streaming to spectators on {}", server.local_addr()?);
+            Some(server)
+        }
+        None => None,
+    };
+
+    let mut reported_breakpoint = None;
+    let mut slow_motion = false;
+    let mut overlay = false;
+    let mut fade_buffer = FadeBuffer::new();
+    let mut clear_count = interpreter.display().clear_count();
+    let mut windowed_size = window.inner_size();
+    // Set when the frame has to be redrawn even if the display didn't change
+    let mut force_redraw = true;
+    let mut flashing = false;
+    let mut cursor_position = None;
+    // The keys held down on the keypad by the mouse (`None`) and each touch
+    let mut pointer_keys: Vec<(Option<u64>, Key)> = Vec::new();
+
+    #[cfg(feature = "gamepad")]
+    let mut gilrs = match gilrs::Gilrs::new() {
+        Ok(gilrs) => Some(gilrs),
+        Err(e) => {
+            eprintln!("Gamepads are unavailable: {}", e);
+            None
+        }
+    };
+
+    let frame_duration = Duration::from_secs_f64(1.0 / fps as f64);
+    let mut last_frame = Instant::now();
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::MainEventsCleared => {
+            #[cfg(feature = "gamepad")]
+            if let Some(gilrs) = &mut gilrs {
+                while let Some(gilrs::Event { event, .. }) = gilrs.next_event() {
+                    let (button, pressed) = match event {
+                        gilrs::EventType::ButtonPressed(button, _) => (button, true),
+                        gilrs::EventType::ButtonReleased(button, _) => (button, false),
+                        _ => continue,
+                    };
+                    if let Some(key) = keymap.get_button(button) {
+                        interpreter.set_key(key, pressed);
+                    }
+                }
+            }
+
+            let now = Instant::now();
+            let next_frame = last_frame + frame_duration;
+            if now < next_frame {
+                *control_flow = event_loop::ControlFlow::WaitUntil(next_frame);
+                return;
+            }
+
+            // A halted ROM only needs its timers to keep running
+            let elapsed = if interpreter.is_halted() {
+                Duration::ZERO
+            } else {
+                now - last_frame
+            };
+            if let Err(e) = interpreter.run_for(elapsed) {
+                eprintln!("Error: {}", e);
+                *control_flow = event_loop::ControlFlow::Exit;
+                return;
+            }
+            last_frame = now;
+            *control_flow = event_loop::ControlFlow::WaitUntil(now + frame_duration);
+
+            // Hires programs switch to a taller display once they start
+            if interpreter.display().height() != display_height {
+                display_height = interpreter.display().height();
+                let height = frame_height(display_height, keypad);
+                frame = vec![0; DisplayBuffer::WIDTH * height * 4];
+                let (buffer_width, buffer_height) = filter.buffer_size(height);
+                pixels.resize_buffer(buffer_width, buffer_height);
+                if window.fullscreen().is_none() {
+                    window.set_inner_size(LogicalSize::new(
+                        DisplayBuffer::WIDTH as u32 * scale,
+                        height as u32 * scale,
+                    ));
+                }
+                force_redraw = true;
+            }
+
+            #[cfg(feature = "net")]
+            if let Some(server) = &mut server {
+                server.broadcast(&interpreter.packed_display());
+            }
+
+            let hit_breakpoint = interpreter.hit_breakpoint();
+            if hit_breakpoint != reported_breakpoint {
+                if let Some(address) = hit_breakpoint {
+                    println!("Breakpoint hit at {:03X}", address);
+                }
+                reported_breakpoint = hit_breakpoint;
+            }
+
+            let display_changed = interpreter.take_display_changed();
+            if instant_clear && interpreter.display().clear_count() != clear_count {
+                fade_buffer.clear();
+            }
+            clear_count = interpreter.display().clear_count();
+            let flash_changed = flash && (interpreter.sound_timer() > 0) != flashing;
+            let fade_changed = fade_buffer.update(interpreter.display().pixels()) && fade;
+            // The overlay shows registers and the keypad shows keys that change without the
+            // display changing
+            if display_changed || fade_changed || flash_changed || force_redraw || overlay || keypad
+            {
+                force_redraw = false;
+                window.request_redraw();
+            }
+        }
+        Event::RedrawRequested(_) => {
+            let display_values = interpreter.display().all_pixel_values();
+            let fade_buffer = if fade { Some(&fade_buffer) } else { None };
+            let display_frame = &mut frame[..display_values.len() * 4];
+            render(&display_values, fade_buffer, display_frame, &palette);
+            // The sound timer works as a visual beep for those who can't hear the buzzer
+            flashing = flash && interpreter.sound_timer() > 0;
+            if flashing {
+                tint(display_frame);
+            }
+            if overlay {
+                draw_overlay(&interpreter, display_frame);
+            }
+            if keypad {
+                draw_keypad(&interpreter, &mut frame);
+            }
+            filter.apply(&frame, pixels.get_frame());
+            pixels.render().unwrap();
+        }
+        Event::WindowEvent {
+            event: event::WindowEvent::CursorMoved { position, .. },
+            ..
+        } => cursor_position = Some(position),
+        Event::WindowEvent {
+            event:
+                event::WindowEvent::MouseInput {
+                    state,
+                    button: event::MouseButton::Left,
+                    ..
+                },
+            ..
+        } if keypad => {
+            let pressed = state == event::ElementState::Pressed;
+            let key = cursor_position
+                .and_then(|position| keypad_key(&pixels, filter, display_height, position));
+            press_pointer(&mut interpreter, &mut pointer_keys, None, key, pressed);
+        }
+        Event::WindowEvent {
+            event: event::WindowEvent::Touch(touch),
+            ..
+        } if keypad => {
+            let pressed = match touch.phase {
+                event::TouchPhase::Started => true,
+                event::TouchPhase::Ended | event::TouchPhase::Cancelled => false,
+                event::TouchPhase::Moved => return,
+            };
+            let key = keypad_key(&pixels, filter, display_height, touch.location);
+            let pointer = Some(touch.id);
+            press_pointer(&mut interpreter, &mut pointer_keys, pointer, key, pressed);
+        }
+        Event::WindowEvent {
+            event:
+                event::WindowEvent::KeyboardInput {
+                    input:
+                        event::KeyboardInput {
+                            virtual_keycode: Some(key_code),
+                            state,
+                            ..
+                        },
+                    ..
+                },
+            ..
+        } => {
+            let pressed = state == event::ElementState::Pressed;
+            match key_code {
+                event::VirtualKeyCode::Escape => *control_flow = event_loop::ControlFlow::Exit,
+                event::VirtualKeyCode::F1 => {
+                    if pressed {
+                        overlay = !overlay;
+                        force_redraw = true;
+                    }
+                }
+                event::VirtualKeyCode::F2 => {
+                    if pressed {
+                        fade = !fade;
+                        force_redraw = true;
+                    }
+                }
+                event::VirtualKeyCode::F11 => {
+                    if pressed {
+                        if window.fullscreen().is_some() {
+                            window.set_fullscreen(None);
+                            window.set_inner_size(windowed_size);
+                        } else {
+                            windowed_size = window.inner_size();
+                            window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+                            if let Some(monitor) = window.current_monitor() {
+                                let size = monitor.size();
+                                pixels.resize_surface(size.width, size.height);
+                            }
+                            force_redraw = true;
+                        }
+                    }
+                }
+                event::VirtualKeyCode::F12 => {
+                    if pressed {
+                        let timestamp = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .map_or(0, |d| d.as_secs());
+                        let path = format!("chippy8-{}.png", timestamp);
+                        match save_screenshot(interpreter.display(), &palette, scale, path.as_ref())
+                        {
+                            Ok(()) => println!("Saved screenshot to {}", path),
+                            Err(e) => eprintln!("Failed to save screenshot: {}", e),
+                        }
+                    }
+                }
+                event::VirtualKeyCode::F5 => {
+                    if pressed {
+                        interpreter.reset();
+                    }
+                }
+                event::VirtualKeyCode::F4 => {
+                    if pressed {
+                        flash = !flash;
+                        force_redraw = true;
+                    }
+                }
+                event::VirtualKeyCode::F3 => {
+                    if pressed {
+                        slow_motion = !slow_motion;
+                        let speed = if slow_motion { SLOW_MOTION_SPEED } else { 1.0 };
+                        interpreter.set_speed_multiplier(speed);
+                    }
+                }
+                event::VirtualKeyCode::Tab => {
+                    let speed = match (pressed, slow_motion) {
+                        (true, _) => TURBO_SPEED,
+                        (false, true) => SLOW_MOTION_SPEED,
+                        (false, false) => 1.0,
+                    };
+                    interpreter.set_speed_multiplier(speed);
+                }
+                event::VirtualKeyCode::Space => {
+                    if pressed {
+                        interpreter.set_paused(!interpreter.is_paused());
+                    }
+                }
+                event::VirtualKeyCode::M => {
+                    if pressed {
+                        interpreter.set_muted(!interpreter.is_muted());
+                    }
+                }
+                event::VirtualKeyCode::B => {
+                    if pressed && interpreter.is_paused() {
+                        if let Err(e) = interpreter.step_frame() {
+                            eprintln!("{:03X}: {}", interpreter.pc(), e);
+                        }
+                    }
+                }
+                event::VirtualKeyCode::O => {
+                    if pressed && interpreter.is_paused() {
+                        let pc = interpreter.pc();
+                        match interpreter.step_over() {
+                            Ok(()) => println!(
+                                "{:03X}: stepped over (next: {:03X})",
+                                pc,
+                                interpreter.pc()
+                            ),
+                            Err(e) => eprintln!("{:03X}: {}", interpreter.pc(), e),
+                        }
+                    }
+                }
+                // Arrow keys are only bound to the keypad by some key maps, so only take over
+                // presses while paused. Releases still reach the key map, so a key held down
+                // when pausing doesn't stay down.
+                event::VirtualKeyCode::Left if pressed && interpreter.is_paused() => {
+                    if interpreter.rewind(1) {
+                        force_redraw = true;
+                    }
+                }
+                event::VirtualKeyCode::Right if pressed && interpreter.is_paused() => {
+                    if interpreter.fast_forward(1) {
+                        force_redraw = true;
+                    }
+                }
+                event::VirtualKeyCode::N => {
+                    if pressed && interpreter.is_paused() {
+                        let pc = interpreter.pc();
+                        match interpreter.step() {
+                            Ok(()) => println!(
+                                "{:03X}: {:04X} (next: {:03X})",
+                                pc,
+                                interpreter.opcode_at(pc),
+                                interpreter.pc()
+                            ),
+                            Err(e) => eprintln!("{:03X}: {}", pc, e),
+                        }
+                    }
+                }
+                _ => {
+                    if let Some(key) = keymap.get(key_code) {
+                        interpreter.set_key(key, pressed);
+                    }
+                }
+            }
+        }
+        Event::WindowEvent {
+            event: event::WindowEvent::Resized(size),
+            ..
+        } => {
+            pixels.resize_surface(size.width, size.height);
+            force_redraw = true;
+        }
+        Event::WindowEvent {
+            event: event::WindowEvent::CloseRequested,
+            ..
+        } => *control_flow = event_loop::ControlFlow::Exit,
+        _ => {}
+    });
+}
+
+/// Returns the keypad key at `position` in the window, if any, with the keypad below a display
+/// `display_height` pixels tall.
+fn keypad_key(
+    pixels: &Pixels,
+    filter: Filter,
+    display_height: usize,
+    position: PhysicalPosition<f64>,
+) -> Option<Key> {
+    let (x, y) = pixels
+        .window_pos_to_pixel((position.x as f32, position.y as f32))
+        .ok()?;
+    key_at(
+        x / filter.factor(),
+        (y / filter.factor()).checked_sub(display_height)?,
+    )
+}
+
+/// Returns the height of the frame in CHIP-8 pixels, with the keypad below the display if it's
+/// shown.
+fn frame_height(display_height: usize, keypad: bool) -> usize {
+    if keypad {
+        display_height + KEYPAD_HEIGHT
+    } else {
+        display_height
+    }
+}
+
+/// Presses `key` on the keypad with `pointer`, or releases the key the pointer is holding.
+fn press_pointer(
+    interpreter: &mut Interpreter,
+    pointer_keys: &mut Vec<(Option<u64>, Key)>,
+    pointer: Option<u64>,
+    key: Option<Key>,
+    pressed: bool,
+) {
+    if let Some(i) = pointer_keys
+        .iter()
+        .position(|&(held_by, _)| held_by == pointer)
+    {
+        let (_, held) = pointer_keys.swap_remove(i);
+        if pointer_keys.iter().all(|&(_, other)| other != held) {
+            interpreter.set_key(held, false);
+        }
+    }
+    if let (true, Some(key)) = (pressed, key) {
+        pointer_keys.push((pointer, key));
+        interpreter.set_key(key, true);
+    }
+}
+
+/// Creates a window for a frame `height` CHIP-8 pixels tall.
+fn create_window(
+    event_loop: &EventLoop<()>,
+    title: &str,
+    scale: u32,
+    height: usize,
+    filter: Filter,
+) -> Result<(Window, Pixels), Chip8Error> {
+    let window = WindowBuilder::new()
+        .with_title(title)
+        .with_inner_size(LogicalSize::new(
+            DisplayBuffer::WIDTH as u32 * scale,
+            height as u32 * scale,
+        ))
+        .build(event_loop)
+        .map_err(|e| Chip8Error::Window(e.to_string()))?;
+
+    let pixels = {
+        let window_size = window.inner_size();
+        let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
+        let (width, height) = filter.buffer_size(height);
+        Pixels::new(width, height, surface_texture)
+            .map_err(|e| Chip8Error::Window(e.to_string()))?
+    };
+
+    Ok((window, pixels))
+}
+
+/// Shows the frames streamed by another instance's `--serve` until the connection closes.
+#[cfg(feature = "net")]
+fn run_spectator(address: &str, args: &Args) -> Result<(), Chip8Error> {
+    let client = crate::net::SpectatorClient::connect(address)?;
+    println!("Watching {}", address);
+
+    let event_loop = EventLoop::new();
+    let (window, mut pixels) = create_window(
+        &event_loop,
+        "CHIP 8 (spectating)",
+        args.scale,
+        DisplayBuffer::HEIGHT,
+        args.filter,
+    )?;
+    let filter = args.filter;
+    let mut frame = vec![0; DisplayBuffer::SIZE * 4];
+    let palette = args.palette;
+    let mut display = DisplayBuffer::new();
+    let frame_duration = Duration::from_secs_f64(1.0 / args.fps as f64);
+
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::MainEventsCleared => {
+            if !client.is_connected() {
+                println!("Connection closed");
+                *control_flow = event_loop::ControlFlow::Exit;
+                return;
+            }
+            if let Some(frame) = client.take_frame() {
+                display = DisplayBuffer::from_packed(&frame);
+                window.request_redraw();
+            }
+            *control_flow = event_loop::ControlFlow::WaitUntil(Instant::now() + frame_duration);
+        }
+        Event::RedrawRequested(_) => {
+            render(&display.pixel_values(), None, &mut frame, &palette);
+            filter.apply(&frame, pixels.get_frame());
+            pixels.render().unwrap();
+        }
+        Event::WindowEvent {
+            event:
+                event::WindowEvent::KeyboardInput {
+                    input:
+                        event::KeyboardInput {
+                            virtual_keycode: Some(event::VirtualKeyCode::Escape),
+                            ..
+                        },
+                    ..
+                },
+            ..
+        }
+        | Event::WindowEvent {
+            event: event::WindowEvent::CloseRequested,
+            ..
+        } => *control_flow = event_loop::ControlFlow::Exit,
+        Event::WindowEvent {
+            event: event::WindowEvent::Resized(size),
+            ..
+        } => {
+            pixels.resize_surface(size.width, size.height);
+            window.request_redraw();
+        }
+        _ => {}
+    });
+}
+
+/// Draws the display to `frame`, mapping pixel values from [`DisplayBuffer::pixel_values`] to
+/// palette colors.
+fn render(
+    display_values: &[u8],
+    fade_buffer: Option<&FadeBuffer>,
+    frame: &mut [u8],
+    palette: &Palette,
+) {
+    if let Some(fade_buffer) = fade_buffer {
+        for (pixel, &intensity) in frame.chunks_exact_mut(4).zip(fade_buffer.intensity()) {
+            pixel.copy_from_slice(&palette.blend(intensity));
+        }
+        return;
+    }
+
+    for (pixel, &value) in frame.chunks_exact_mut(4).zip(display_values.iter()) {
+        pixel.copy_from_slice(&palette.color(value));
+    }
+}
+
+/// Tints `frame` towards [`FLASH_COLOR`].
+fn tint(frame: &mut [u8]) {
+    for pixel in frame.chunks_exact_mut(4) {
+        for (channel, &flash) in pixel[..3].iter_mut().zip(&FLASH_COLOR) {
+            let mixed = *channel as u16 * (255 - FLASH_STRENGTH) + flash as u16 * FLASH_STRENGTH;
+            *channel = (mixed / 255) as u8;
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::interpreter::{decode, Instruction};
+
+/// Disassembles a ROM into a list of instructions.
+///
+/// Each entry contains the address of the instruction, assuming the ROM is loaded at 0x200, and
+/// a line with the raw bytes and the mnemonic, e.g. `6A 02 -> LD V10, 0x02`. XO-CHIP's
+/// `F000 nnnn` takes up four bytes, e.g. `F0 00 12 34 -> LD I, LONG 0x1234`.
+pub fn disassemble(bytes: &[u8]) -> Vec<(u16, String)> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let address = 0x200 + offset as u16;
+        let (line, len) = match bytes[offset..] {
+            [0xF0, 0x00, upper, lower, ..] => (
+                format!(
+                    "F0 00 {:02X} {:02X} -> LD I, LONG 0x{:04X}",
+                    upper,
+                    lower,
+                    u16::from_be_bytes([upper, lower])
+                ),
+                4,
+            ),
+            [upper, lower, ..] => (
+                format!(
+                    "{:02X} {:02X} -> {}",
+                    upper,
+                    lower,
+                    mnemonic(u16::from_be_bytes([upper, lower]))
+                ),
+                2,
+            ),
+            [byte] => (format!("{:02X}    -> DB 0x{:02X}", byte, byte), 1),
+            [] => unreachable!(),
+        };
+        lines.push((address, line));
+        offset += len;
+    }
+    lines
+}
+
+/// Returns the mnemonic for a single opcode.
+fn mnemonic(opcode: u16) -> String {
+    use Instruction::*;
+
+    let instruction = match decode(opcode) {
+        Some(instruction) => instruction,
+        None => return format!("DB 0x{:02X}, 0x{:02X}", opcode >> 8, opcode & 0xFF),
+    };
+    match instruction {
+        ClearScreen => "CLS".to_string(),
+        Return => "RET".to_string(),
+        MachineRoutine(nnn) => format!("SYS 0x{:03X}", nnn),
+        Jump(nnn) => format!("JP 0x{:03X}", nnn),
+        Call(nnn) => format!("CALL 0x{:03X}", nnn),
+        SkipIfEq { x, kk } => format!("SE V{}, 0x{:02X}", x, kk),
+        SkipIfNe { x, kk } => format!("SNE V{}, 0x{:02X}", x, kk),
+        SkipIfRegEq { x, y } => format!("SE V{}, V{}", x, y),
+        SaveRange { x, y } => format!("SAVE V{}, V{}", x, y),
+        LoadRange { x, y } => format!("LOAD V{}, V{}", x, y),
+        SetReg { x, kk } => format!("LD V{}, 0x{:02X}", x, kk),
+        AddReg { x, kk } => format!("ADD V{}, 0x{:02X}", x, kk),
+        Copy { x, y } => format!("LD V{}, V{}", x, y),
+        Or { x, y } => format!("OR V{}, V{}", x, y),
+        And { x, y } => format!("AND V{}, V{}", x, y),
+        Xor { x, y } => format!("XOR V{}, V{}", x, y),
+        Add { x, y } => format!("ADD V{}, V{}", x, y),
+        Sub { x, y } => format!("SUB V{}, V{}", x, y),
+        ShiftRight { x, y } => format!("SHR V{}, V{}", x, y),
+        SubReversed { x, y } => format!("SUBN V{}, V{}", x, y),
+        ShiftLeft { x, y } => format!("SHL V{}, V{}", x, y),
+        SkipIfRegNe { x, y } => format!("SNE V{}, V{}", x, y),
+        SetI(nnn) => format!("LD I, 0x{:03X}", nnn),
+        JumpOffset(nnn) => format!("JP V0, 0x{:03X}", nnn),
+        Random { x, kk } => format!("RND V{}, 0x{:02X}", x, kk),
+        Draw { x, y, n } => format!("DRW V{}, V{}, {}", x, y, n),
+        SkipIfKey { x } => format!("SKP V{}", x),
+        SkipIfNotKey { x } => format!("SKNP V{}", x),
+        SetILong => "LD I, LONG".to_string(),
+        SelectPlanes { n } => format!("PLANE {}", n),
+        GetDelay { x } => format!("LD V{}, DT", x),
+        WaitKey { x } => format!("LD V{}, K", x),
+        SetDelay { x } => format!("LD DT, V{}", x),
+        SetSound { x } => format!("LD ST, V{}", x),
+        AddI { x } => format!("ADD I, V{}", x),
+        FontChar { x } => format!("LD F, V{}", x),
+        Bcd { x } => format!("LD B, V{}", x),
+        Store { x } => format!("LD [I], V{}", x),
+        Load { x } => format!("LD V{}, [I]", x),
+    }
+}
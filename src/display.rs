@@ -1,57 +1,406 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde_big_array::BigArray;
+
 const WIDTH: usize = 64;
 const HEIGHT: usize = 32;
 const DISPLAY_SIZE: usize = WIDTH * HEIGHT;
+/// The height of the 64x64 mode of hires COSMAC VIP programs.
+const HIRES_HEIGHT: usize = 64;
+const HIRES_SIZE: usize = WIDTH * HIRES_HEIGHT;
+
+/// The number of bit planes, as on XO-CHIP.
+const PLANES: usize = 2;
+
+/// One bit plane of the display, with room for the rows of hires mode.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Plane(#[cfg_attr(feature = "serde", serde(with = "BigArray"))] [bool; HIRES_SIZE]);
 
+/// How sprite pixels are combined with the pixels already on the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DrawMode {
+    /// Lit sprite pixels toggle the display pixels, as on all real interpreters. The only mode
+    /// that reports collisions.
+    #[default]
+    Xor,
+    /// Lit sprite pixels turn the display pixels on.
+    Or,
+    /// The sprite overwrites the display, turning pixels off where the sprite is unlit.
+    Set,
+}
+
+/// The display, made of two overlapping bit planes.
+///
+/// Plain CHIP-8 programs only ever draw to the first plane. XO-CHIP programs can select either or
+/// both planes, giving each pixel one of four colors.
+///
+/// The display is 64x32 pixels, or 64x64 in the hires mode of some COSMAC VIP programs. Methods
+/// returning fixed-size arrays only cover the top 32 rows; use [`DisplayBuffer::pixels`] and
+/// [`DisplayBuffer::all_pixel_values`] to get every row.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct DisplayBuffer {
-    buffer: [bool; DISPLAY_SIZE],
+    planes: [Plane; PLANES],
+    /// A bit mask of the planes that drawing and clearing affect.
+    selected_planes: u8,
+    /// Whether the display is 64 pixels tall.
+    hires: bool,
+    /// Whether the buffer has changed since `take_dirty` was last called.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    dirty: bool,
+    /// Incremented on every change, see [`DisplayBuffer::version`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    version: u64,
+    /// The number of times [`DisplayBuffer::clear`] has been called.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    clear_count: u64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    draw_mode: DrawMode,
 }
 
 impl DisplayBuffer {
+    pub const WIDTH: usize = WIDTH;
+    pub const HEIGHT: usize = HEIGHT;
+    /// The height in hires mode, see [`DisplayBuffer::set_hires`].
+    pub const HIRES_HEIGHT: usize = HIRES_HEIGHT;
     pub const SIZE: usize = DISPLAY_SIZE;
+    /// The number of bytes in [`DisplayBuffer::as_packed`].
+    pub const PACKED_SIZE: usize = DISPLAY_SIZE / 8;
+    pub const PLANES: usize = PLANES;
 
     pub fn new() -> Self {
         DisplayBuffer {
-            buffer: [false; DISPLAY_SIZE],
+            planes: [Plane([false; HIRES_SIZE]), Plane([false; HIRES_SIZE])],
+            selected_planes: 0b01,
+            hires: false,
+            dirty: true,
+            version: 0,
+            clear_count: 0,
+            draw_mode: DrawMode::Xor,
+        }
+    }
+
+    /// Sets how sprites are combined with the display. Defaults to [`DrawMode::Xor`].
+    pub fn set_draw_mode(&mut self, mode: DrawMode) {
+        self.draw_mode = mode;
+    }
+
+    pub fn draw_mode(&self) -> DrawMode {
+        self.draw_mode
+    }
+
+    /// Returns whether the buffer has changed since the last call, and resets the flag.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+
+    /// Marks the buffer as changed, e.g. after it has been replaced as a whole.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+        self.version += 1;
+    }
+
+    /// Replaces the pixels and selected planes with those of `other`, counting it as a change.
+    pub fn replace(&mut self, other: &DisplayBuffer) {
+        self.planes = other.planes.clone();
+        self.selected_planes = other.selected_planes;
+        self.hires = other.hires;
+        self.mark_dirty();
+    }
+
+    /// Switches between the 64x32 display and the 64x64 display of hires COSMAC VIP programs,
+    /// clearing every plane.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        for plane in &mut self.planes {
+            plane.0 = [false; HIRES_SIZE];
+        }
+        self.mark_dirty();
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    /// Returns the number of rows, [`DisplayBuffer::HIRES_HEIGHT`] in hires mode and
+    /// [`DisplayBuffer::HEIGHT`] otherwise.
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            HEIGHT
         }
     }
 
+    /// Returns a counter that increases every time the buffer changes.
+    ///
+    /// Unlike [`DisplayBuffer::take_dirty`], any number of observers can poll it by comparing it
+    /// to the last version they saw.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Returns the number of times the display has been cleared, e.g. for a [`FadeBuffer`] that
+    /// skips the fade when the screen is cleared.
+    pub fn clear_count(&self) -> u64 {
+        self.clear_count
+    }
+
+    /// Returns the pixels of the first 32 rows of the first plane in row-major order, where
+    /// `true` means the pixel is lit.
     pub fn buffer(&self) -> &[bool; DISPLAY_SIZE] {
-        &self.buffer
+        self.plane(0)
+    }
+
+    /// Returns the pixels of every row of the first plane, like [`DisplayBuffer::buffer`] but
+    /// including the bottom half in hires mode.
+    pub fn pixels(&self) -> &[bool] {
+        &self.planes[0].0[..WIDTH * self.height()]
+    }
+
+    /// Returns the pixels of plane `index`, like [`DisplayBuffer::buffer`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` isn't 0 or 1.
+    pub fn plane(&self, index: usize) -> &[bool; DISPLAY_SIZE] {
+        self.planes[index].0[..DISPLAY_SIZE].try_into().unwrap()
+    }
+
+    /// Returns the pixels in row-major order as 2-bit values, where bit `n` is set if the pixel
+    /// is lit in plane `n`.
+    pub fn pixel_values(&self) -> [u8; DISPLAY_SIZE] {
+        let mut values = [0; DISPLAY_SIZE];
+        self.write_pixel_values(&mut values);
+        values
+    }
+
+    /// Returns the pixel values like [`DisplayBuffer::pixel_values`], including the bottom half
+    /// in hires mode.
+    pub fn all_pixel_values(&self) -> Vec<u8> {
+        let mut values = vec![0; WIDTH * self.height()];
+        self.write_pixel_values(&mut values);
+        values
+    }
+
+    fn write_pixel_values(&self, values: &mut [u8]) {
+        for (bit, plane) in self.planes.iter().enumerate() {
+            for (value, &lit) in values.iter_mut().zip(&plane.0) {
+                *value |= (lit as u8) << bit;
+            }
+        }
+    }
+
+    /// Renders the display as text, one line per row with `█` for lit pixels and a space for
+    /// unlit ones. A pixel counts as lit if it's lit in any plane.
+    pub fn to_ascii(&self) -> String {
+        self.to_ascii_with('█', ' ')
+    }
+
+    /// Renders the display like [`DisplayBuffer::to_ascii`], with `on` for lit pixels and `off`
+    /// for unlit ones, e.g. `'#'` and `'.'` for plain ASCII.
+    pub fn to_ascii_with(&self, on: char, off: char) -> String {
+        let values = self.all_pixel_values();
+        let mut text = String::with_capacity((WIDTH + 1) * self.height());
+        for row in values.chunks_exact(WIDTH) {
+            text.extend(row.iter().map(|&value| if value != 0 { on } else { off }));
+            text.push('\n');
+        }
+        text
     }
 
-    pub fn write_sprite(&mut self, sprite: &[u8], x: usize, y: usize) -> bool {
-        let mut collision = false;
+    /// Renders the display as text with two rows per line, using the half block characters `▀`
+    /// and `▄`, so that the pixels come out roughly square in a terminal.
+    pub fn to_half_blocks(&self) -> String {
+        let values = self.all_pixel_values();
+        let mut text = String::with_capacity((WIDTH + 1) * self.height() / 2);
+        for rows in values.chunks_exact(WIDTH * 2) {
+            let (top, bottom) = rows.split_at(WIDTH);
+            text.extend(top.iter().zip(bottom).map(|(&top, &bottom)| {
+                match (top != 0, bottom != 0) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                }
+            }));
+            text.push('\n');
+        }
+        text
+    }
 
-        for (offset_y, &byte) in sprite.iter().enumerate() {
-            for (offset_x, &bit) in to_bits(byte).iter().enumerate() {
-                if self.set_pos(x + offset_x, y + offset_y, bit) {
-                    collision = true;
+    /// Selects the planes that drawing and clearing affect, as a bit mask where bit `n` selects
+    /// plane `n`. Only the first plane is selected initially.
+    pub fn select_planes(&mut self, mask: u8) {
+        self.selected_planes = mask & 0b11;
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    /// Returns the pixels of the first 32 rows of the first plane packed into bytes, 8 pixels per
+    /// byte with the leftmost pixel in the most significant bit, row by row.
+    pub fn as_packed(&self) -> [u8; DISPLAY_SIZE / 8] {
+        let mut packed = [0; DISPLAY_SIZE / 8];
+        for (byte, pixels) in packed.iter_mut().zip(self.planes[0].0.chunks_exact(8)) {
+            *byte = pixels
+                .iter()
+                .fold(0, |byte, &pixel| (byte << 1) | pixel as u8);
+        }
+        packed
+    }
+
+    /// Creates a buffer with the first plane set from pixels packed by
+    /// [`DisplayBuffer::as_packed`].
+    pub fn from_packed(packed: &[u8; DISPLAY_SIZE / 8]) -> Self {
+        let mut display = DisplayBuffer::new();
+        for (pixels, &byte) in display.planes[0].0.chunks_exact_mut(8).zip(packed) {
+            pixels.copy_from_slice(&to_bits(byte));
+        }
+        display
+    }
+
+    /// Draws `sprite` onto the selected planes with its top left corner at (`x`, `y`) using the
+    /// draw mode, and returns the number of rows in which a lit pixel was turned off, summed over
+    /// the planes. Only [`DrawMode::Xor`] turns pixels off this way.
+    ///
+    /// With more than one plane selected, `sprite` holds the rows for each plane in turn, e.g.
+    /// the first half for the first plane and the second half for the second plane.
+    ///
+    /// Pixels past the edge of the display are dropped, or wrapped around to the opposite edge if
+    /// `wrap` is set.
+    pub fn write_sprite(&mut self, sprite: &[u8], x: usize, y: usize, wrap: bool) -> u8 {
+        let plane_count = self.selected_planes.count_ones() as usize;
+        if plane_count == 0 || sprite.len() < plane_count {
+            return 0;
+        }
+        let rows_per_plane = sprite.len() / plane_count;
+        let mut collided_rows = 0;
+        // Cleared so that it tells whether this sprite changed anything
+        let was_dirty = std::mem::replace(&mut self.dirty, false);
+
+        let planes = selected(self.selected_planes);
+        for (plane, rows) in planes.zip(sprite.chunks_exact(rows_per_plane)) {
+            for (offset_y, &byte) in rows.iter().enumerate() {
+                let mut collision = false;
+                for (offset_x, &bit) in to_bits(byte).iter().enumerate() {
+                    let (x, y) = (x + offset_x, y + offset_y);
+                    let (x, y) = if wrap {
+                        (x % WIDTH, y % self.height())
+                    } else {
+                        (x, y)
+                    };
+                    collision |= self.set_pos(plane, x, y, bit);
                 }
+                collided_rows += collision as u8;
             }
         }
 
-        collision
+        if self.dirty {
+            self.version += 1;
+        }
+        self.dirty |= was_dirty;
+        collided_rows
     }
 
+    /// Turns off every pixel in the selected planes.
     pub fn clear(&mut self) {
-        for b in &mut self.buffer[..] {
-            *b = false;
+        for plane in selected(self.selected_planes) {
+            self.planes[plane].0 = [false; HIRES_SIZE];
         }
+        self.clear_count += 1;
+        self.mark_dirty();
     }
 
-    fn set_pos(&mut self, x: usize, y: usize, val: bool) -> bool {
-        if x >= WIDTH || y >= HEIGHT {
+    fn set_pos(&mut self, plane: usize, x: usize, y: usize, val: bool) -> bool {
+        if x >= WIDTH || y >= self.height() {
             return false;
         }
 
         let index = y * WIDTH + x;
+        let pixel = &mut self.planes[plane].0[index];
+
+        let old = *pixel;
+        *pixel = match self.draw_mode {
+            DrawMode::Xor => old ^ val,
+            DrawMode::Or => old | val,
+            DrawMode::Set => val,
+        };
+        self.dirty |= *pixel != old;
+        self.draw_mode == DrawMode::Xor && old & val
+    }
+}
 
-        let collision = self.buffer[index] & val;
-        self.buffer[index] ^= val;
-        collision
+impl Default for DisplayBuffer {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
+/// Per-pixel brightness that fades out over a few frames after a pixel is turned off.
+///
+/// Used alongside a [`DisplayBuffer`] to reduce the flicker caused by games erasing and
+/// redrawing sprites.
+#[derive(Clone)]
+pub struct FadeBuffer {
+    intensity: [u8; HIRES_SIZE],
+}
+
+impl FadeBuffer {
+    /// How much the intensity of an unlit pixel decreases each frame.
+    const DECAY: u8 = 64;
+
+    pub fn new() -> Self {
+        FadeBuffer {
+            intensity: [0; HIRES_SIZE],
+        }
+    }
+
+    /// Advances one frame: lit pixels get full intensity and unlit pixels fade.
+    ///
+    /// `buffer` holds the pixels of every row, e.g. from [`DisplayBuffer::pixels`]. Returns
+    /// whether any intensity changed.
+    pub fn update(&mut self, buffer: &[bool]) -> bool {
+        let mut changed = false;
+        for (intensity, &lit) in self.intensity.iter_mut().zip(buffer.iter()) {
+            let new = if lit {
+                u8::MAX
+            } else {
+                intensity.saturating_sub(Self::DECAY)
+            };
+            changed |= new != *intensity;
+            *intensity = new;
+        }
+        changed
+    }
+
+    /// Turns every pixel off at once instead of letting them fade, e.g. when the display is
+    /// cleared and the frontend wants the screen to go blank immediately.
+    pub fn clear(&mut self) {
+        self.intensity = [0; HIRES_SIZE];
+    }
+
+    /// Returns the intensity of each pixel, from 0 (off) to 255 (fully lit), with room for the
+    /// rows of hires mode.
+    pub fn intensity(&self) -> &[u8; HIRES_SIZE] {
+        &self.intensity
+    }
+}
+
+impl Default for FadeBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the indices of the planes selected by `mask`, in ascending order.
+fn selected(mask: u8) -> impl Iterator<Item = usize> {
+    (0..PLANES).filter(move |plane| mask & (1 << plane) != 0)
+}
+
 fn to_bits(byte: u8) -> [bool; 8] {
     [
         (byte >> 7) == 1,
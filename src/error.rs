@@ -0,0 +1,170 @@
+use crate::{
+    asm::AsmError,
+    interpreter::ExecuteError,
+    memory::{InvalidFontAddress, InvalidFontLength, LoadRomError, MemoryOutOfBounds},
+    palette::ParseColorError,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+/// Any error returned by the public API of the crate, e.g. by [`run`](crate::run).
+///
+/// Functions that can only fail in one way return the specific error instead, which converts
+/// into this one with `?`. Every [`ExecuteError`] gets a variant of its own, so memory accesses
+/// past the last address look the same whether they come from an instruction or e.g. from
+/// [`Interpreter::peek`](crate::Interpreter::peek).
+#[derive(Debug)]
+pub enum Chip8Error {
+    /// The ROM couldn't be loaded into memory, e.g. because it's too large.
+    LoadRom(LoadRomError),
+    /// The opcode does not decode to any known instruction.
+    UnknownOpcode(u16),
+    /// A subroutine was called with the stack already full.
+    StackOverflow,
+    /// A subroutine returned with no return address on the stack.
+    StackUnderflow,
+    /// Memory was accessed at an address past the last one.
+    AddressOutOfRange(usize),
+    /// A jump or call targeted an odd address. Only reported in strict mode.
+    UnalignedJump(u16),
+    /// The program counter points past the last complete instruction in memory.
+    PcOutOfRange(usize),
+    /// A custom font had the wrong length.
+    InvalidFont(InvalidFontLength),
+    /// The font couldn't be moved, because it would overlap the program area.
+    FontAddress(InvalidFontAddress),
+    /// Assembling a program failed.
+    Asm(AsmError),
+    /// A color couldn't be parsed.
+    ParseColor(ParseColorError),
+    /// A saved state couldn't be written or read.
+    #[cfg(feature = "serde")]
+    State(bincode::Error),
+    /// Reading the ROM or another file failed.
+    Io(io::Error),
+    /// The command line arguments or config file are invalid.
+    Config(String),
+    /// The window couldn't be created.
+    Window(String),
+    /// The display didn't have the expected SHA-1 hash after a headless run.
+    DisplayMismatch { expected: String, actual: String },
+}
+
+impl Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::LoadRom(e) => write!(f, "could not load the ROM: {}", e),
+            Chip8Error::UnknownOpcode(opcode) => ExecuteError::UnknownOpcode(*opcode).fmt(f),
+            Chip8Error::StackOverflow => ExecuteError::StackOverflow.fmt(f),
+            Chip8Error::StackUnderflow => ExecuteError::StackUnderflow.fmt(f),
+            Chip8Error::AddressOutOfRange(address) => {
+                MemoryOutOfBounds { address: *address }.fmt(f)
+            }
+            Chip8Error::UnalignedJump(address) => ExecuteError::UnalignedJump(*address).fmt(f),
+            Chip8Error::PcOutOfRange(address) => ExecuteError::PcOutOfRange(*address).fmt(f),
+            Chip8Error::InvalidFont(e) => e.fmt(f),
+            Chip8Error::FontAddress(e) => e.fmt(f),
+            Chip8Error::Asm(e) => write!(f, "could not assemble the program: {}", e),
+            Chip8Error::ParseColor(e) => e.fmt(f),
+            #[cfg(feature = "serde")]
+            Chip8Error::State(e) => write!(f, "could not save or load the state: {}", e),
+            Chip8Error::Io(e) => e.fmt(f),
+            Chip8Error::Config(message) => f.write_str(message),
+            Chip8Error::Window(message) => write!(f, "could not create the window: {}", message),
+            Chip8Error::DisplayMismatch { expected, actual } => write!(
+                f,
+                "expected the display to have SHA-1 {}, but it has {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Chip8Error::LoadRom(e) => Some(e),
+            Chip8Error::Io(e) => Some(e),
+            Chip8Error::InvalidFont(e) => Some(e),
+            Chip8Error::FontAddress(e) => Some(e),
+            Chip8Error::Asm(e) => Some(e),
+            Chip8Error::ParseColor(e) => Some(e),
+            #[cfg(feature = "serde")]
+            Chip8Error::State(e) => Some(e),
+            Chip8Error::UnknownOpcode(_)
+            | Chip8Error::StackOverflow
+            | Chip8Error::StackUnderflow
+            | Chip8Error::AddressOutOfRange(_)
+            | Chip8Error::UnalignedJump(_)
+            | Chip8Error::PcOutOfRange(_)
+            | Chip8Error::Config(_)
+            | Chip8Error::Window(_)
+            | Chip8Error::DisplayMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<LoadRomError> for Chip8Error {
+    fn from(e: LoadRomError) -> Self {
+        Chip8Error::LoadRom(e)
+    }
+}
+
+impl From<ExecuteError> for Chip8Error {
+    fn from(e: ExecuteError) -> Self {
+        match e {
+            ExecuteError::UnknownOpcode(opcode) => Chip8Error::UnknownOpcode(opcode),
+            ExecuteError::StackOverflow => Chip8Error::StackOverflow,
+            ExecuteError::StackUnderflow => Chip8Error::StackUnderflow,
+            ExecuteError::MemoryOutOfBounds(address) => Chip8Error::AddressOutOfRange(address),
+            ExecuteError::UnalignedJump(address) => Chip8Error::UnalignedJump(address),
+            ExecuteError::PcOutOfRange(address) => Chip8Error::PcOutOfRange(address),
+        }
+    }
+}
+
+impl From<MemoryOutOfBounds> for Chip8Error {
+    fn from(e: MemoryOutOfBounds) -> Self {
+        Chip8Error::AddressOutOfRange(e.address)
+    }
+}
+
+impl From<InvalidFontLength> for Chip8Error {
+    fn from(e: InvalidFontLength) -> Self {
+        Chip8Error::InvalidFont(e)
+    }
+}
+
+impl From<InvalidFontAddress> for Chip8Error {
+    fn from(e: InvalidFontAddress) -> Self {
+        Chip8Error::FontAddress(e)
+    }
+}
+
+impl From<AsmError> for Chip8Error {
+    fn from(e: AsmError) -> Self {
+        Chip8Error::Asm(e)
+    }
+}
+
+impl From<ParseColorError> for Chip8Error {
+    fn from(e: ParseColorError) -> Self {
+        Chip8Error::ParseColor(e)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<bincode::Error> for Chip8Error {
+    fn from(e: bincode::Error) -> Self {
+        Chip8Error::State(e)
+    }
+}
+
+impl From<io::Error> for Chip8Error {
+    fn from(e: io::Error) -> Self {
+        Chip8Error::Io(e)
+    }
+}
@@ -0,0 +1,101 @@
+use crate::display::DisplayBuffer;
+
+/// How many window buffer pixels wide and tall a CHIP-8 pixel is with the CRT and smooth filters.
+const UPSCALE: usize = 4;
+/// How bright the last row of every CHIP-8 pixel is with the CRT filter, out of 255.
+const SCANLINE_BRIGHTNESS: u16 = 140;
+
+/// How the display is upscaled to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Crisp square pixels.
+    #[default]
+    Nearest,
+    /// Square pixels with dark scanlines between the rows, like an old CRT.
+    Crt,
+    /// Bilinear interpolation between pixels.
+    Smooth,
+}
+
+impl Filter {
+    pub const NAMES: [&'static str; 3] = ["nearest", "crt", "smooth"];
+
+    /// Returns the filter called `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Filter> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "crt" => Some(Filter::Crt),
+            "smooth" => Some(Filter::Smooth),
+            _ => None,
+        }
+    }
+
+    /// Returns the width and height of the buffer the filter draws a frame `height` pixels tall
+    /// to.
+    ///
+    /// Nearest-neighbor scaling is left to `pixels`, the other filters upscale the display
+    /// themselves so they have room for detail between the CHIP-8 pixels.
+    pub fn buffer_size(self, height: usize) -> (u32, u32) {
+        let factor = self.factor();
+        (
+            (DisplayBuffer::WIDTH * factor) as u32,
+            (height * factor) as u32,
+        )
+    }
+
+    /// Draws `source`, an RGBA frame as wide as the display, to `target`, an RGBA frame of
+    /// [`Filter::buffer_size`].
+    pub fn apply(self, source: &[u8], target: &mut [u8]) {
+        let height = source.len() / 4 / DisplayBuffer::WIDTH;
+        match self {
+            Filter::Nearest => target.copy_from_slice(source),
+            Filter::Crt => {
+                let width = DisplayBuffer::WIDTH * UPSCALE;
+                for (i, pixel) in target.chunks_exact_mut(4).enumerate() {
+                    let (x, y) = (i % width, i / width);
+                    let source_index = (y / UPSCALE * DisplayBuffer::WIDTH + x / UPSCALE) * 4;
+                    pixel.copy_from_slice(&source[source_index..source_index + 4]);
+                    if y % UPSCALE == UPSCALE - 1 {
+                        for channel in &mut pixel[..3] {
+                            *channel = (*channel as u16 * SCANLINE_BRIGHTNESS / 255) as u8;
+                        }
+                    }
+                }
+            }
+            Filter::Smooth => {
+                let width = DisplayBuffer::WIDTH * UPSCALE;
+                for (i, pixel) in target.chunks_exact_mut(4).enumerate() {
+                    let (x, y) = (i % width, i / width);
+                    let (x0, x1, tx) = sample_position(x, DisplayBuffer::WIDTH);
+                    let (y0, y1, ty) = sample_position(y, height);
+                    for channel in 0..4 {
+                        let at = |x: usize, y: usize| {
+                            source[(y * DisplayBuffer::WIDTH + x) * 4 + channel] as f32
+                        };
+                        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+                        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+                        pixel[channel] = (top * (1.0 - ty) + bottom * ty).round() as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns how many buffer pixels wide and tall a CHIP-8 pixel is.
+    pub fn factor(self) -> usize {
+        match self {
+            Filter::Nearest => 1,
+            Filter::Crt | Filter::Smooth => UPSCALE,
+        }
+    }
+}
+
+/// Returns the two source pixels to interpolate between for the upscaled coordinate `position`,
+/// and how far towards the second one it is.
+fn sample_position(position: usize, len: usize) -> (usize, usize, f32) {
+    // Sample at the center of the upscaled pixel, in source pixel units
+    let center = ((position as f32 + 0.5) / UPSCALE as f32 - 0.5).max(0.0);
+    let first = (center as usize).min(len - 1);
+    let second = (first + 1).min(len - 1);
+    (first, second, center - first as f32)
+}
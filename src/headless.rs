@@ -0,0 +1,57 @@
+use crate::{args::Args, save_screenshot, Chip8Error, Interpreter};
+
+/// The seed used by `--run-frames` unless `--seed` is given, so runs are reproducible.
+const DEFAULT_SEED: u64 = 0;
+
+/// Runs `frames` frames of `interpreter` without a window, e.g. to check ROMs in CI, then does
+/// what the arguments say with the display.
+///
+/// Fails if an instruction fails, e.g. on an unknown opcode, or if the display doesn't match
+/// `--expect-display`.
+pub fn run_headless(
+    mut interpreter: Interpreter,
+    frames: u32,
+    args: &Args,
+) -> Result<(), Chip8Error> {
+    if args.seed.is_none() {
+        interpreter.set_seed(DEFAULT_SEED);
+    }
+    for frame in 0..frames {
+        interpreter.step_frame().inspect_err(|e| {
+            eprintln!("{:03X}: {} in frame {}", interpreter.pc(), e, frame + 1);
+        })?;
+    }
+
+    // Hashes every row of both planes, so hires and XO-CHIP displays are checked in full
+    let hash = sha1_smol::Sha1::from(interpreter.display().all_pixel_values())
+        .digest()
+        .to_string();
+    println!(
+        "Ran {} frames, {} instructions. Display SHA-1: {}",
+        frames,
+        interpreter.cycle_count(),
+        hash
+    );
+    if args.dump_display {
+        print!("{}", interpreter.display().to_ascii_with('#', '.'));
+    }
+    if let Some(path) = &args.screenshot {
+        save_screenshot(
+            interpreter.display(),
+            &args.palette,
+            args.scale,
+            path.as_ref(),
+        )
+        .map_err(|e| Chip8Error::Io(std::io::Error::other(e)))?;
+        eprintln!("Saved screenshot to {}", path);
+    }
+    match &args.expect_display {
+        Some(expected) if !expected.eq_ignore_ascii_case(&hash) => {
+            Err(Chip8Error::DisplayMismatch {
+                expected: expected.clone(),
+                actual: hash,
+            })
+        }
+        _ => Ok(()),
+    }
+}
@@ -1,59 +1,353 @@
+mod breakpoint;
+mod decode;
 mod fetch_execute;
+mod history;
 mod instructions;
+mod profile;
+mod state;
+mod timing;
+mod trace;
 
-use crate::{display::DisplayBuffer, keyboard::KeyboardState, memory::Memory, timer::Timers};
+pub use decode::{decode, Instruction};
+pub use fetch_execute::ExecuteError;
+pub(crate) use fetch_execute::Opcode;
+pub use instructions::ControlFlow;
+pub use state::{InterpreterState, InvalidState};
+pub use timing::Timing;
+pub use trace::TraceEntry;
+
+use crate::{
+    clock::{Clock, SystemClock},
+    display::{DisplayBuffer, DrawMode},
+    keyboard::{Key, KeyboardState},
+    memory::{
+        InvalidFontAddress, InvalidFontLength, LoadRomError, Memory, MemoryOutOfBounds,
+        DEFAULT_ENTRY_POINT, DEFAULT_FONT, DEFAULT_MEMORY_SIZE, FONT_SIZE, XOCHIP_MEMORY_SIZE,
+    },
+    quirks::Quirks,
+    stack::Stack,
+    timer::Timers,
+};
+use rand::{rngs::StdRng, SeedableRng};
 use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Range,
     thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
-use winit::event::VirtualKeyCode;
 
+/// The longest time [`Interpreter::run_for`] runs instructions for in one call.
+const MAX_RUN_DURATION: Duration = Duration::from_millis(250);
+/// The length of one frame at the 60 Hz rate of the timers.
+const FRAME_DURATION: Duration = Duration::from_micros(16_667);
+
+/// A write by the program to its own code, at or below the instruction doing the write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfModifyingWrite {
+    /// The address of the instruction that wrote the byte.
+    pub pc: usize,
+    /// The address that was written to.
+    pub target: usize,
+    pub value: u8,
+}
+
+/// The state of the interpreter at the end of a 60 Hz frame, passed to the frame handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// The number of frames since the interpreter was created or reset, including this one.
+    pub frame: u64,
+    pub cycle_count: u64,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+}
+
+/// A change to the display made by an instruction, passed to the draw handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawEvent {
+    /// `00E0` cleared the display.
+    Clear,
+    /// `Dxyn` drew a sprite `height` rows tall with its top left corner at `(x, y)`.
+    Sprite {
+        x: usize,
+        y: usize,
+        height: u8,
+        /// Whether the sprite turned off any lit pixels.
+        collision: bool,
+    },
+}
+
+/// What to do when the interpreter encounters an opcode it doesn't know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownOpcodePolicy {
+    /// Stop execution and return the error from `run_cycle`.
+    Halt,
+    /// Skip the two bytes and continue with the next instruction, see
+    /// [`Interpreter::set_skipped_opcode_handler`].
+    Skip,
+}
+
+/// A CHIP-8 interpreter with its own memory, display, timers and keyboard state.
+///
+/// The interpreter doesn't own a window; a frontend drives it with [`Interpreter::run_cycle`] or
+/// [`Interpreter::step`], feeds it key presses and reads the display buffer.
 pub struct Interpreter {
     memory: Memory,
     display_buf: DisplayBuffer,
     timers: Timers,
     keyboard_state: KeyboardState,
+    rom: Vec<u8>,
+    /// The font written to memory on reset.
+    font: [u8; FONT_SIZE],
 
-    stack: Vec<u16>,
+    stack: Stack,
     program_counter: usize,
+    /// Where the ROM is loaded and execution starts.
+    entry_point: usize,
     reg_i: u16,
     reg_v: [u8; 16],
 
+    clock: Box<dyn Clock>,
+    rng: StdRng,
     cycle_delay: Duration,
-    last_cycle: Instant,
+    /// How many times faster than normal instructions run and timers count down.
+    speed_multiplier: f32,
+    /// The clock reading when `run_cycle` last executed an instruction.
+    last_cycle: Duration,
+    /// Clock cycles that `run_for` owes, including fractions of a cycle.
+    pending_cycles: f64,
+    timing: Timing,
+    /// The timer tick during which `Dxyn` last waited for the display, see
+    /// [`Quirks::display_wait`].
+    display_wait_tick: Option<u64>,
+    /// Whether `run_cycle` and `run_for` count the timers down from the clock, see
+    /// [`Interpreter::set_auto_tick_timers`].
+    auto_tick_timers: bool,
+    /// The number of instructions executed since the interpreter was created or reset.
+    cycle_count: u64,
+    /// Whether the last instruction was a `1nnn` jumping to itself.
+    halted: bool,
+    paused: bool,
+    muted: bool,
+
+    unknown_opcode_policy: UnknownOpcodePolicy,
+    quirks: Quirks,
+    /// The memory size set with [`Interpreter::set_memory_size`], which takes precedence over
+    /// the size the quirks call for.
+    memory_size: Option<usize>,
+    /// Whether questionable operands are errors, see [`Interpreter::set_strict`].
+    strict: bool,
+
+    trace_enabled: bool,
+    trace: VecDeque<TraceEntry>,
+
+    profiling_enabled: bool,
+    opcode_counts: HashMap<&'static str, u64>,
+
+    /// The states from before the most recent steps, see [`Interpreter::set_step_history`].
+    history: VecDeque<InterpreterState>,
+    history_capacity: usize,
+    /// The states and cycle counts at the most recent frames, see
+    /// [`Interpreter::set_rewind_history`].
+    frame_history: VecDeque<(InterpreterState, u64)>,
+    frame_history_capacity: usize,
+    /// The index in `frame_history` of the state rewound to, if any.
+    rewind_position: Option<usize>,
+
+    breakpoints: HashSet<usize>,
+    hit_breakpoint: Option<usize>,
+
+    machine_routine_handler: Option<Box<dyn FnMut(u16)>>,
+    self_modifying_write_handler: Option<Box<dyn FnMut(SelfModifyingWrite)>>,
+    halt_handler: Option<Box<dyn FnMut(usize)>>,
+    skipped_opcode_handler: Option<Box<dyn FnMut(u16)>>,
+    frame_handler: Option<Box<dyn FnMut(FrameInfo)>>,
+    draw_handler: Option<Box<dyn FnMut(DrawEvent)>>,
+
+    /// Keys pressed with [`Interpreter::press_for`], and the timer tick to release them at.
+    key_releases: Vec<(Key, u64)>,
+
+    /// Whether the sound timer was active when the sound handlers were last considered.
+    sound_active: bool,
+    sound_start_handler: Option<Box<dyn FnMut()>>,
+    sound_stop_handler: Option<Box<dyn FnMut()>>,
 }
 
 impl Interpreter {
+    /// Creates an interpreter with `rom` loaded at address 0x200.
+    ///
+    /// The quirks are chosen with [`Quirks::detect`], falling back to [`Quirks::default`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ROM is empty or doesn't fit in memory, see [`Interpreter::try_new`].
+    #[deprecated(note = "use `Interpreter::try_new`, which returns an error instead of panicking")]
     pub fn new(rom: &[u8]) -> Self {
-        let mut memory = Memory::new();
-        memory.load_rom(rom);
+        Self::try_new(rom).unwrap_or_else(|e| panic!("invalid ROM: {}", e))
+    }
+
+    /// Creates an interpreter with `rom` loaded at address 0x200, returning an error if the ROM
+    /// is empty or doesn't fit in memory.
+    ///
+    /// The quirks are chosen with [`Quirks::detect`], falling back to [`Quirks::default`].
+    pub fn try_new(rom: &[u8]) -> Result<Self, LoadRomError> {
+        Self::with_rng(rom, StdRng::from_entropy(), DEFAULT_ENTRY_POINT)
+    }
+
+    /// Creates an interpreter like [`Interpreter::try_new`], with `rom` loaded and execution
+    /// starting at `entry_point` instead of 0x200, e.g. 0x600 for ETI-660 programs.
+    ///
+    /// Returns an error if the entry point is below 0x200 or past the end of memory, or if the ROM
+    /// doesn't fit after it.
+    pub fn with_entry_point(rom: &[u8], entry_point: u16) -> Result<Self, LoadRomError> {
+        Self::with_rng(rom, StdRng::from_entropy(), entry_point as usize)
+    }
+
+    /// Creates an interpreter like [`Interpreter::try_new`], with the random number generator
+    /// used by `Cxkk` seeded with `seed`. Runs with the same seed and input are identical.
+    pub fn with_seed(rom: &[u8], seed: u64) -> Result<Self, LoadRomError> {
+        Self::with_rng(rom, StdRng::seed_from_u64(seed), DEFAULT_ENTRY_POINT)
+    }
 
-        Interpreter {
+    /// Seeds the random number generator used by `Cxkk` with `seed`, like
+    /// [`Interpreter::with_seed`].
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn with_rng(rom: &[u8], rng: StdRng, entry_point: usize) -> Result<Self, LoadRomError> {
+        let quirks = Quirks::detect(rom).unwrap_or_default();
+        let mut memory = Memory::with_size(default_memory_size(&quirks));
+        memory.load_rom(rom, entry_point)?;
+        let clock = SystemClock::new();
+        let now = clock.now();
+
+        Ok(Interpreter {
             memory,
             display_buf: DisplayBuffer::new(),
-            timers: Timers::new(),
+            timers: Timers::new(now),
             keyboard_state: KeyboardState::new(),
+            rom: rom.to_vec(),
+            font: DEFAULT_FONT,
 
-            stack: Vec::with_capacity(16),
-            program_counter: 0x200,
+            stack: Stack::new(),
+            program_counter: entry_point,
+            entry_point,
             reg_i: 0,
             reg_v: [0; 16],
 
+            clock: Box::new(clock),
+            rng,
             cycle_delay: Duration::from_millis(2),
-            last_cycle: Instant::now(),
-        }
+            speed_multiplier: 1.0,
+            last_cycle: now,
+            pending_cycles: 0.0,
+            timing: Timing::Uniform,
+            display_wait_tick: None,
+            auto_tick_timers: true,
+            cycle_count: 0,
+            halted: false,
+            paused: false,
+            muted: false,
+
+            unknown_opcode_policy: UnknownOpcodePolicy::Halt,
+            quirks,
+            memory_size: None,
+            strict: false,
+
+            trace_enabled: false,
+            trace: VecDeque::new(),
+
+            profiling_enabled: false,
+            opcode_counts: HashMap::new(),
+
+            history: VecDeque::new(),
+            history_capacity: 0,
+            frame_history: VecDeque::new(),
+            frame_history_capacity: 0,
+            rewind_position: None,
+
+            breakpoints: HashSet::new(),
+            hit_breakpoint: None,
+
+            machine_routine_handler: None,
+            self_modifying_write_handler: None,
+            halt_handler: None,
+            skipped_opcode_handler: None,
+            frame_handler: None,
+            draw_handler: None,
+
+            key_releases: Vec::new(),
+            sound_active: false,
+            sound_start_handler: None,
+            sound_stop_handler: None,
+        })
+    }
+
+    /// Restarts the loaded ROM, clearing memory, registers, the stack, the display, the timers and
+    /// the keyboard state.
+    pub fn reset(&mut self) {
+        let font_address = self.memory.font_address();
+        self.memory = Memory::with_size(self.memory.size());
+        self.memory
+            .set_font_address(font_address)
+            .expect("the font address has already been validated");
+        self.memory
+            .set_font(&self.font)
+            .expect("the font has already been validated");
+        self.memory
+            .load_rom(&self.rom, self.entry_point)
+            .expect("the ROM has already been loaded once");
+        self.display_buf.replace(&DisplayBuffer::new());
+        self.timers = Timers::new(self.clock.now());
+        self.keyboard_state.release_all();
+        self.key_releases.clear();
+
+        self.stack.clear();
+        self.program_counter = self.entry_point;
+        self.reg_i = 0;
+        self.reg_v = [0; 16];
+        self.cycle_count = 0;
+        self.history.clear();
+        self.frame_history.clear();
+        self.rewind_position = None;
+        self.halted = false;
+        self.display_wait_tick = None;
+        self.update_sound();
+    }
+
+    /// Replaces the loaded ROM with `rom` and resets, keeping the configuration such as the
+    /// quirks, clock rate, font and breakpoints.
+    pub fn load(&mut self, rom: &[u8]) -> Result<(), LoadRomError> {
+        self.memory.check_rom(rom, self.entry_point)?;
+        self.rom = rom.to_vec();
+        self.reset();
+        Ok(())
     }
 
-    pub fn run_cycle(&mut self) {
+    /// Executes the next instruction if enough time has passed since the last one, and ticks the
+    /// timers. Sleeps otherwise.
+    ///
+    /// While paused, neither instructions nor timers run.
+    pub fn run_cycle(&mut self) -> Result<(), ExecuteError> {
+        if self.paused {
+            thread::sleep(self.cycle_delay);
+            return Ok(());
+        }
+
         // TODO: Implement proper clock rate
-        let now = Instant::now();
-        let diff = now - self.last_cycle;
-        let timers_diff = self.timers.tick();
+        let now = self.clock.now();
+        let diff = now.saturating_sub(self.last_cycle);
+        let timers_diff = self.tick_timers_from_clock(now);
+
+        if self.waiting_for_display() {
+            thread::sleep(self.cycle_delay);
+            return Ok(());
+        }
 
-        if diff > self.cycle_delay {
+        let delay = self.cycle_delay.mul_f64(self.next_instruction_cost());
+        if diff > delay.div_f32(self.speed_multiplier) {
             self.last_cycle = now;
-            let opcode = self.fetch();
-            self.execute(opcode);
+            self.step()?;
         } else {
             let smallest_diff = if diff > timers_diff {
                 diff
@@ -62,14 +356,613 @@ impl Interpreter {
             };
             thread::sleep(smallest_diff);
         }
+
+        Ok(())
     }
 
+    /// Executes as many instructions as the clock rate allows in `elapsed`, and ticks the
+    /// timers. Meant to be called once per frame by a frontend.
+    ///
+    /// Stops early when a breakpoint is hit, or when `Dxyn` waits for the next frame with
+    /// [`Quirks::display_wait`]. While paused, neither instructions nor timers run.
+    pub fn run_for(&mut self, elapsed: Duration) -> Result<(), ExecuteError> {
+        if self.paused {
+            return Ok(());
+        }
+
+        self.tick_timers_from_clock(self.clock.now());
+
+        // Don't try to catch up after long stalls, e.g. while the window is being dragged
+        let elapsed = elapsed.min(MAX_RUN_DURATION);
+        self.pending_cycles +=
+            elapsed.as_secs_f64() * self.speed_multiplier as f64 / self.cycle_delay.as_secs_f64();
+        while self.pending_cycles >= self.next_instruction_cost() {
+            if self.paused || self.waiting_for_display() {
+                self.pending_cycles = 0.0;
+                break;
+            }
+
+            self.pending_cycles -= self.next_instruction_cost();
+            self.step()?;
+        }
+
+        Ok(())
+    }
+
+    /// Executes exactly one instruction, regardless of the clock and of whether the interpreter
+    /// is paused.
+    ///
+    /// If the instruction has a breakpoint that hasn't fired yet, nothing is executed and the
+    /// interpreter is paused instead. The next call executes the instruction.
+    pub fn step(&mut self) -> Result<(), ExecuteError> {
+        let pc = self.program_counter;
+        if self.breakpoints.contains(&pc) && self.hit_breakpoint != Some(pc) {
+            self.hit_breakpoint = Some(pc);
+            self.paused = true;
+            return Ok(());
+        }
+        self.hit_breakpoint = None;
+
+        self.discard_rewound_frames();
+        // Only kept once the step succeeds, so a failed step leaves nothing to undo
+        let previous_state = (self.history_capacity > 0).then(|| self.snapshot());
+        let opcode = self.fetch()?;
+        let opcode_value = opcode.value();
+        if self.profiling_enabled {
+            self.record_opcode(&opcode);
+        }
+        let result = self.execute(opcode);
+        self.update_halted(pc, opcode_value, &result);
+
+        if self.trace_enabled {
+            self.record_trace(TraceEntry {
+                pc,
+                opcode: opcode_value,
+                control_flow: result.ok(),
+            });
+        }
+
+        match result {
+            Err(ExecuteError::UnknownOpcode(opcode))
+                if self.unknown_opcode_policy == UnknownOpcodePolicy::Skip =>
+            {
+                if let Some(handler) = &mut self.skipped_opcode_handler {
+                    handler(opcode);
+                }
+            }
+            Err(e) => {
+                // Stay on the offending instruction
+                self.program_counter -= 2;
+                return Err(e);
+            }
+            Ok(_) => (),
+        }
+        if let Some(state) = previous_state {
+            self.record_history(state);
+        }
+        self.cycle_count += 1;
+        self.update_sound();
+
+        Ok(())
+    }
+
+    /// Executes one 60 Hz frame worth of instructions at the configured clock rate and counts
+    /// the timers down once, regardless of whether the interpreter is paused.
+    ///
+    /// Stops early when a breakpoint is hit, or when `Dxyn` waits for the next frame.
+    pub fn step_frame(&mut self) -> Result<(), ExecuteError> {
+        let cycles = (FRAME_DURATION.as_secs_f64() / self.cycle_delay.as_secs_f64()).round();
+        let mut remaining = cycles.max(1.0);
+        while remaining > 0.0 {
+            if self.waiting_for_display() {
+                break;
+            }
+            remaining -= self.next_instruction_cost();
+            self.step()?;
+            if self.hit_breakpoint.is_some() {
+                return Ok(());
+            }
+        }
+        self.tick_timers();
+
+        Ok(())
+    }
+
+    /// Counts the delay and sound timers down by one step, regardless of the time passed.
+    ///
+    /// Meant for frontends that keep their own 60 Hz clock, e.g. a browser's animation frames,
+    /// and drive the interpreter with [`Interpreter::step`]. Also lets tests step the timers
+    /// deterministically, without waiting for real time to pass.
+    pub fn tick_timers(&mut self) {
+        let ticks = self.timers.ticks();
+        self.timers.decrement();
+        self.update_sound();
+        self.release_keys();
+        self.record_frame();
+        self.notify_frames(ticks);
+    }
+
+    /// Sets whether [`Interpreter::run_cycle`] and [`Interpreter::run_for`] count the timers down
+    /// at 60 Hz by the clock. Defaults to `true`.
+    ///
+    /// Frontends that own the 60 Hz tick, e.g. a browser's animation frames, can turn it off and
+    /// call [`Interpreter::tick_timers`] themselves.
+    pub fn set_auto_tick_timers(&mut self, enabled: bool) {
+        if enabled && !self.auto_tick_timers {
+            // Don't catch up on the time spent ticking manually
+            self.timers.restart(self.clock.now());
+        }
+        self.auto_tick_timers = enabled;
+    }
+
+    /// Pauses or resumes [`Interpreter::run_cycle`], e.g. after a breakpoint paused it.
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused && !paused {
+            // Don't catch up on the time spent paused
+            self.timers.restart(self.clock.now());
+        }
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns whether the last instruction was a `1nnn` jumping to its own address, the idle
+    /// loop many ROMs end with.
+    ///
+    /// Stepping a halted interpreter just executes the jump again, so frontends can stop stepping
+    /// and only keep the timers and display running.
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Mutes or unmutes the buzzer.
+    ///
+    /// Muting only affects [`Interpreter::is_sound_playing`]; the sound timer keeps counting down
+    /// as usual, so games behave the same whether muted or not.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Returns whether the buzzer should sound, i.e. whether the sound timer is active and the
+    /// interpreter isn't muted.
+    pub fn is_sound_playing(&self) -> bool {
+        self.timers.sound_timer > 0 && !self.muted
+    }
+
+    /// Returns the number of instructions executed since the interpreter was created or reset.
+    ///
+    /// Instructions that fail aren't counted, while unknown opcodes skipped with
+    /// [`UnknownOpcodePolicy::Skip`] are.
+    pub fn cycle_count(&self) -> u64 {
+        self.cycle_count
+    }
+
+    /// Returns the address of the next instruction to be executed.
+    pub fn pc(&self) -> usize {
+        self.program_counter
+    }
+
+    /// Returns the values of the registers V0 through VF.
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.reg_v
+    }
+
+    /// Returns the value of the I register.
+    pub fn reg_i(&self) -> u16 {
+        self.reg_i
+    }
+
+    /// Replaces the font used by `Fx29` with `font`, which must be 80 bytes: 5 bytes for each
+    /// hex digit from 0 through F. The font is kept when the interpreter is reset.
+    pub fn set_font(&mut self, font: &[u8]) -> Result<(), InvalidFontLength> {
+        self.memory.set_font(font)?;
+        self.font.copy_from_slice(font);
+        Ok(())
+    }
+
+    /// Moves the font to `address`, 0x050 by default. Some ROMs read the font bytes directly
+    /// and expect them at a specific address. The address is kept when the interpreter is reset.
+    ///
+    /// Returns an error if the font would overlap the program area starting at 0x200.
+    pub fn set_font_address(&mut self, address: u16) -> Result<(), InvalidFontAddress> {
+        self.memory.set_font_address(address as usize)
+    }
+
+    /// Returns the value of the delay timer.
+    pub fn delay_timer(&self) -> u8 {
+        self.timers.delay_timer
+    }
+
+    /// Returns the value of the sound timer.
+    pub fn sound_timer(&self) -> u8 {
+        self.timers.sound_timer
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.timers.delay_timer = value;
+    }
+
+    /// Sets the sound timer, starting or stopping the buzzer like `Fx18` does.
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.timers.sound_timer = value;
+        self.update_sound();
+    }
+
+    /// Returns the number of return addresses on the call stack. Calls fail with
+    /// [`ExecuteError::StackOverflow`] once it reaches 16, the depth of the original hardware.
+    pub fn stack_depth(&self) -> usize {
+        self.stack.frames().len()
+    }
+
+    /// Returns the return addresses on the call stack, starting with the oldest.
+    pub fn stack_frames(&self) -> &[u16] {
+        self.stack.frames()
+    }
+
+    /// Returns the memory in `range`, e.g. for a memory viewer.
+    pub fn memory_slice(&self, range: Range<usize>) -> Result<&[u8], MemoryOutOfBounds> {
+        let len = range.end.saturating_sub(range.start);
+        self.memory.dump(range.start, len)
+    }
+
+    /// Returns the byte at `address`.
+    pub fn peek(&self, address: u16) -> Result<u8, MemoryOutOfBounds> {
+        self.memory.dump(address as usize, 1).map(|bytes| bytes[0])
+    }
+
+    /// Writes `value` to `address`, e.g. to cheat by freezing a lives counter or to set up a
+    /// memory state to test.
+    ///
+    /// Unlike writes by instructions, this doesn't call the self-modifying write handler.
+    pub fn poke(&mut self, address: u16, value: u8) -> Result<(), MemoryOutOfBounds> {
+        let address = address as usize;
+        if address >= self.memory.size() {
+            return Err(MemoryOutOfBounds { address });
+        }
+        self.memory.write_byte(address, value);
+        Ok(())
+    }
+
+    /// Sets how many instructions are executed per second. Defaults to 500.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hz` is zero.
+    pub fn set_clock_hz(&mut self, hz: u32) {
+        assert!(hz > 0, "clock rate must be larger than zero");
+        self.cycle_delay = Duration::from_secs_f64(1.0 / hz as f64);
+    }
+
+    /// Sets how many instructions are executed per 60 Hz frame, an alternative to
+    /// [`Interpreter::set_clock_hz`] for speeds given the way many ROMs document them. `ipf`
+    /// instructions per frame are about `ipf * 60` Hz, e.g. Octo's default of 11 is about 660 Hz.
+    ///
+    /// [`Interpreter::step_frame`] runs exactly `ipf` instructions, unless it stops early.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ipf` is zero.
+    pub fn set_instructions_per_frame(&mut self, ipf: u32) {
+        assert!(ipf > 0, "instructions per frame must be larger than zero");
+        self.cycle_delay = FRAME_DURATION / ipf;
+    }
+
+    /// Runs instructions and counts the timers down `multiplier` times faster than normal, e.g.
+    /// 5.0 to fast-forward or 0.1 for slow motion. Defaults to 1.0.
+    ///
+    /// The timers are scaled along with the clock rate, so games keep their usual pacing relative
+    /// to the instructions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiplier` isn't a positive, finite number.
+    pub fn set_speed_multiplier(&mut self, multiplier: f32) {
+        assert!(
+            multiplier > 0.0 && multiplier.is_finite(),
+            "speed multiplier must be positive and finite"
+        );
+        self.speed_multiplier = multiplier;
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        self.speed_multiplier
+    }
+
+    /// Replaces the clock that `run_cycle` and the timers read, e.g. with a fake one in tests.
+    /// Defaults to [`SystemClock`].
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        let now = clock.now();
+        self.clock = clock;
+        self.last_cycle = now;
+        self.timers.restart(now);
+    }
+
+    /// Sets a handler called with the address when a `0nnn` instruction is executed.
+    ///
+    /// `0nnn` calls a machine code routine on the original hardware. Such routines are never
+    /// executed; without a handler the instruction does nothing.
+    pub fn set_machine_routine_handler(&mut self, handler: Option<Box<dyn FnMut(u16)>>) {
+        self.machine_routine_handler = handler;
+    }
+
+    /// Sets a handler called when an instruction writes to memory between the entry point and its
+    /// own address, which usually means the program is rewriting its code.
+    ///
+    /// Without a handler, writes aren't checked at all.
+    pub fn set_self_modifying_write_handler(
+        &mut self,
+        handler: Option<Box<dyn FnMut(SelfModifyingWrite)>>,
+    ) {
+        self.self_modifying_write_handler = handler;
+    }
+
+    /// Sets a handler called with the address of the jump when the interpreter becomes halted,
+    /// see [`Interpreter::is_halted`].
+    pub fn set_halt_handler(&mut self, handler: Option<Box<dyn FnMut(usize)>>) {
+        self.halt_handler = handler;
+    }
+
+    /// Sets a handler called with the opcode when an unknown opcode is skipped, see
+    /// [`UnknownOpcodePolicy::Skip`].
+    pub fn set_skipped_opcode_handler(&mut self, handler: Option<Box<dyn FnMut(u16)>>) {
+        self.skipped_opcode_handler = handler;
+    }
+
+    /// Sets a handler called once per 60 Hz frame, right after the timers count down, e.g. to
+    /// update audio, overlays or input in lockstep with the interpreter.
+    ///
+    /// If the timers catch up on several frames at once, the handler is called once for each.
+    pub fn set_frame_handler(&mut self, handler: Option<Box<dyn FnMut(FrameInfo)>>) {
+        self.frame_handler = handler;
+    }
+
+    /// Sets a handler called when `00E0` clears the display or `Dxyn` draws a sprite, e.g. to
+    /// announce changes to the screen or play audio cues.
+    pub fn set_draw_handler(&mut self, handler: Option<Box<dyn FnMut(DrawEvent)>>) {
+        self.draw_handler = handler;
+    }
+
+    /// Sets a handler called when the sound timer becomes active, e.g. to start a vibration or
+    /// show a visual beep. It's called even while muted.
+    pub fn set_sound_start_handler(&mut self, handler: Option<Box<dyn FnMut()>>) {
+        self.sound_start_handler = handler;
+    }
+
+    /// Sets a handler called when the sound timer reaches zero after being active.
+    pub fn set_sound_stop_handler(&mut self, handler: Option<Box<dyn FnMut()>>) {
+        self.sound_stop_handler = handler;
+    }
+
+    /// Sets how unknown opcodes are handled. Defaults to [`UnknownOpcodePolicy::Halt`].
+    pub fn set_unknown_opcode_policy(&mut self, policy: UnknownOpcodePolicy) {
+        self.unknown_opcode_policy = policy;
+    }
+
+    /// Enables or disables strict mode, a developer aid for catching bugs in ROMs.
+    ///
+    /// In strict mode, jumps and calls to odd addresses fail with
+    /// [`ExecuteError::UnalignedJump`], and `Dxyn` reading sprite data past the end of memory
+    /// fails with [`ExecuteError::MemoryOutOfBounds`] instead of wrapping around to address 0.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Sets the quirks used when executing instructions.
+    ///
+    /// Switching XO-CHIP opcodes on or off also resizes the memory to 64 KiB or 4 KiB, unless a
+    /// size was set with [`Interpreter::set_memory_size`]. Fails, leaving the quirks unchanged,
+    /// if the loaded ROM doesn't fit in 4 KiB.
+    pub fn set_quirks(&mut self, quirks: Quirks) -> Result<(), LoadRomError> {
+        if self.memory_size.is_none() && quirks.xochip_opcodes != self.quirks.xochip_opcodes {
+            self.resize_memory(default_memory_size(&quirks))?;
+        }
+        self.quirks = quirks;
+        Ok(())
+    }
+
+    /// Resizes the memory to `size` bytes, keeping its contents. The size is kept when the
+    /// interpreter is reset or the quirks change.
+    ///
+    /// Fails, leaving the memory unchanged, if `size` is too small to hold the loaded ROM.
+    pub fn set_memory_size(&mut self, size: usize) -> Result<(), LoadRomError> {
+        self.resize_memory(size)?;
+        self.memory_size = Some(size);
+        Ok(())
+    }
+
+    fn resize_memory(&mut self, size: usize) -> Result<(), LoadRomError> {
+        let max = size.saturating_sub(self.entry_point);
+        if self.rom.len() > max {
+            return Err(LoadRomError::TooLarge {
+                len: self.rom.len(),
+                max,
+            });
+        }
+        self.memory.resize(size);
+        Ok(())
+    }
+
+    /// Returns the number of bytes of memory.
+    pub fn memory_size(&self) -> usize {
+        self.memory.size()
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Returns the current contents of the display, or of its top 32 rows in hires mode.
     pub fn get_display_buffer(&self) -> &[bool; DisplayBuffer::SIZE] {
         self.display_buf.buffer()
     }
 
-    pub fn handle_input(&mut self, key_code: VirtualKeyCode, pressed: bool) {
-        self.keyboard_state.handle_input(key_code, pressed);
+    /// Returns the current contents of both display planes, as described in
+    /// [`DisplayBuffer::pixel_values`].
+    pub fn get_display_values(&self) -> [u8; DisplayBuffer::SIZE] {
+        self.display_buf.pixel_values()
+    }
+
+    /// Sets how `Dxyn` combines sprites with the display. Defaults to [`DrawMode::Xor`]; the
+    /// other modes are for experimenting and never report collisions.
+    pub fn set_draw_mode(&mut self, mode: DrawMode) {
+        self.display_buf.set_draw_mode(mode);
+    }
+
+    /// Returns the display, e.g. to render it with [`DisplayBuffer::to_ascii`].
+    pub fn display(&self) -> &DisplayBuffer {
+        &self.display_buf
+    }
+
+    /// Returns the current contents of the display packed into bytes, as described in
+    /// [`DisplayBuffer::as_packed`].
+    pub fn packed_display(&self) -> [u8; DisplayBuffer::PACKED_SIZE] {
+        self.display_buf.as_packed()
+    }
+
+    /// Returns a counter that increases every time the display changes, e.g. after `00E0` or a
+    /// `Dxyn` that lit or erased a pixel. Frontends can compare it to the last version they
+    /// drew instead of consuming [`Interpreter::take_display_changed`].
+    pub fn display_version(&self) -> u64 {
+        self.display_buf.version()
+    }
+
+    /// Returns whether the display has changed since the last call.
+    pub fn take_display_changed(&mut self) -> bool {
+        self.display_buf.take_dirty()
+    }
+
+    /// Sets whether `key` is pressed, cancelling any release scheduled with
+    /// [`Interpreter::press_for`].
+    pub fn set_key(&mut self, key: Key, pressed: bool) {
+        self.key_releases.retain(|&(held, _)| held != key);
+        self.keyboard_state.set_key(key, pressed);
+    }
+
+    /// Presses `key` and releases it after `frames` 60 Hz frames, i.e. after the timers have
+    /// counted down that many times. Releases after one frame if `frames` is 0.
+    ///
+    /// Makes it easy to script input, e.g. for demos and tests.
+    pub fn press_for(&mut self, key: Key, frames: u32) {
+        self.set_key(key, true);
+        let release_at = self.timers.ticks() + frames.max(1) as u64;
+        self.key_releases.push((key, release_at));
+    }
+
+    /// Returns whether `key` is pressed.
+    pub fn is_key_pressed(&self, key: Key) -> bool {
+        self.keyboard_state.key[key.hex() as usize]
+    }
+
+    /// Returns whether a `Dxyn` is waiting for the next timer tick before execution continues.
+    fn waiting_for_display(&self) -> bool {
+        self.display_wait_tick == Some(self.timers.ticks())
+    }
+
+    /// Writes `value` to `address` for the current instruction, reporting self-modifying writes.
+    fn write_memory(&mut self, address: usize, value: u8) {
+        if let Some(handler) = &mut self.self_modifying_write_handler {
+            // The program counter has already moved past the instruction
+            let pc = self.program_counter - 2;
+            if (self.entry_point..=pc).contains(&address) {
+                handler(SelfModifyingWrite {
+                    pc,
+                    target: address,
+                    value,
+                });
+            }
+        }
+        self.memory.write_byte(address, value);
+    }
+
+    /// Counts the timers down for the time passed until `now`, if they're ticked automatically.
+    ///
+    /// Returns the time since the last tick if no period has passed, see [`Timers::tick`].
+    fn tick_timers_from_clock(&mut self, now: Duration) -> Duration {
+        if !self.auto_tick_timers {
+            return Duration::ZERO;
+        }
+        let ticks = self.timers.ticks();
+        let diff = self.timers.tick(now, self.speed_multiplier);
+        self.update_sound();
+        self.release_keys();
+        if self.timers.ticks() > ticks {
+            self.record_frame();
+        }
+        self.notify_frames(ticks);
+        diff
+    }
+
+    /// Releases the keys pressed with [`Interpreter::press_for`] whose time is up.
+    fn release_keys(&mut self) {
+        let ticks = self.timers.ticks();
+        let keyboard_state = &mut self.keyboard_state;
+        self.key_releases.retain(|&(key, release_at)| {
+            let release = ticks >= release_at;
+            if release {
+                keyboard_state.set_key(key, false);
+            }
+            !release
+        });
+    }
+
+    /// Calls the frame handler for every frame since the timers had counted down `ticks` times.
+    fn notify_frames(&mut self, ticks: u64) {
+        if let Some(handler) = &mut self.frame_handler {
+            for frame in ticks + 1..=self.timers.ticks() {
+                handler(FrameInfo {
+                    frame,
+                    cycle_count: self.cycle_count,
+                    delay_timer: self.timers.delay_timer,
+                    sound_timer: self.timers.sound_timer,
+                });
+            }
+        }
+    }
+
+    /// Updates whether the interpreter is halted after executing `opcode` at `pc`, calling the
+    /// halt handler if it just became halted.
+    fn update_halted(
+        &mut self,
+        pc: usize,
+        opcode: u16,
+        result: &Result<ControlFlow, ExecuteError>,
+    ) {
+        let halted = result.is_ok()
+            && matches!(decode(opcode), Some(Instruction::Jump(nnn)) if nnn as usize == pc);
+        if halted && !self.halted {
+            if let Some(handler) = &mut self.halt_handler {
+                handler(pc);
+            }
+        }
+        self.halted = halted;
+    }
+
+    /// Calls the sound start or stop handler if the sound timer has become active or inactive.
+    fn update_sound(&mut self) {
+        let active = self.timers.sound_timer > 0;
+        if active == self.sound_active {
+            return;
+        }
+        self.sound_active = active;
+
+        let handler = if active {
+            &mut self.sound_start_handler
+        } else {
+            &mut self.sound_stop_handler
+        };
+        if let Some(handler) = handler {
+            handler();
+        }
     }
 
     /// Returns a copy of the value in register `v`.
@@ -82,3 +975,12 @@ impl Interpreter {
         self.reg_v.get_mut(index as usize).unwrap()
     }
 }
+
+/// Returns the memory size of the platform that `quirks` describe.
+fn default_memory_size(quirks: &Quirks) -> usize {
+    if quirks.xochip_opcodes {
+        XOCHIP_MEMORY_SIZE
+    } else {
+        DEFAULT_MEMORY_SIZE
+    }
+}
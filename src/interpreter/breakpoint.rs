@@ -0,0 +1,47 @@
+use super::{ExecuteError, Interpreter};
+
+/// The most instructions [`Interpreter::step_over`] runs while waiting for a call to return.
+const STEP_OVER_MAX_CYCLES: u64 = 1_000_000;
+
+impl Interpreter {
+    /// Adds a breakpoint that pauses execution right before the instruction at `address`.
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address as usize);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&(address as usize));
+    }
+
+    /// Executes the next instruction like [`Interpreter::step`], except that a `CALL` runs until
+    /// the subroutine returns.
+    ///
+    /// Stops early when a breakpoint is hit inside the subroutine, or after a million
+    /// instructions in case the subroutine never returns.
+    pub fn step_over(&mut self) -> Result<(), ExecuteError> {
+        let pc = self.program_counter;
+        // `step` reports a program counter past the end of memory
+        if pc + 1 >= self.memory.size() || self.opcode_at(pc) & 0xF000 != 0x2000 {
+            return self.step();
+        }
+
+        // Recursive calls pass the return address with a deeper stack
+        let depth = self.stack_depth();
+        let return_address = pc + 2;
+        for _ in 0..STEP_OVER_MAX_CYCLES {
+            self.step()?;
+            let returned = self.program_counter == return_address && self.stack_depth() == depth;
+            if returned || self.hit_breakpoint.is_some() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the address of the breakpoint that paused execution, if execution hasn't
+    /// continued since.
+    pub fn hit_breakpoint(&self) -> Option<usize> {
+        self.hit_breakpoint
+    }
+}
@@ -0,0 +1,206 @@
+/// A decoded CHIP-8 instruction.
+///
+/// `x` and `y` are register indices, `kk` is an 8-bit immediate value, `n` a 4-bit one and
+/// `nnn` a 12-bit address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// `00E0`
+    ClearScreen,
+    /// `00EE`
+    Return,
+    /// `0nnn`
+    MachineRoutine(u16),
+    /// `1nnn`
+    Jump(u16),
+    /// `2nnn`
+    Call(u16),
+    /// `3xkk`
+    SkipIfEq { x: u8, kk: u8 },
+    /// `4xkk`
+    SkipIfNe { x: u8, kk: u8 },
+    /// `5xy0`
+    SkipIfRegEq { x: u8, y: u8 },
+    /// `5xy2`, XO-CHIP only
+    SaveRange { x: u8, y: u8 },
+    /// `5xy3`, XO-CHIP only
+    LoadRange { x: u8, y: u8 },
+    /// `6xkk`
+    SetReg { x: u8, kk: u8 },
+    /// `7xkk`
+    AddReg { x: u8, kk: u8 },
+    /// `8xy0`
+    Copy { x: u8, y: u8 },
+    /// `8xy1`
+    Or { x: u8, y: u8 },
+    /// `8xy2`
+    And { x: u8, y: u8 },
+    /// `8xy3`
+    Xor { x: u8, y: u8 },
+    /// `8xy4`
+    Add { x: u8, y: u8 },
+    /// `8xy5`
+    Sub { x: u8, y: u8 },
+    /// `8xy6`
+    ShiftRight { x: u8, y: u8 },
+    /// `8xy7`
+    SubReversed { x: u8, y: u8 },
+    /// `8xyE`
+    ShiftLeft { x: u8, y: u8 },
+    /// `9xy0`
+    SkipIfRegNe { x: u8, y: u8 },
+    /// `Annn`
+    SetI(u16),
+    /// `Bnnn`
+    JumpOffset(u16),
+    /// `Cxkk`
+    Random { x: u8, kk: u8 },
+    /// `Dxyn`
+    Draw { x: u8, y: u8, n: u8 },
+    /// `Ex9E`
+    SkipIfKey { x: u8 },
+    /// `ExA1`
+    SkipIfNotKey { x: u8 },
+    /// `F000 nnnn`, XO-CHIP only. The address is in the two bytes after the opcode.
+    SetILong,
+    /// `Fn01`, XO-CHIP only
+    SelectPlanes { n: u8 },
+    /// `Fx07`
+    GetDelay { x: u8 },
+    /// `Fx0A`
+    WaitKey { x: u8 },
+    /// `Fx15`
+    SetDelay { x: u8 },
+    /// `Fx18`
+    SetSound { x: u8 },
+    /// `Fx1E`
+    AddI { x: u8 },
+    /// `Fx29`
+    FontChar { x: u8 },
+    /// `Fx33`
+    Bcd { x: u8 },
+    /// `Fx55`
+    Store { x: u8 },
+    /// `Fx65`
+    Load { x: u8 },
+}
+
+/// Decodes `opcode`, or returns `None` if it isn't a known instruction.
+///
+/// XO-CHIP instructions are always decoded; whether they're executed depends on
+/// [`Quirks::xochip_opcodes`](crate::Quirks::xochip_opcodes).
+pub fn decode(opcode: u16) -> Option<Instruction> {
+    use Instruction::*;
+
+    let nibbles = [
+        (opcode >> 12) as u8,
+        (opcode >> 8 & 0xF) as u8,
+        (opcode >> 4 & 0xF) as u8,
+        (opcode & 0xF) as u8,
+    ];
+    let nnn = opcode & 0x0FFF;
+    let kk = opcode as u8;
+
+    let instruction = match nibbles {
+        [0x0, 0x0, 0xE, 0x0] => ClearScreen,
+        [0x0, 0x0, 0xE, 0xE] => Return,
+        [0x0, ..] => MachineRoutine(nnn),
+        [0x1, ..] => Jump(nnn),
+        [0x2, ..] => Call(nnn),
+        [0x3, x, ..] => SkipIfEq { x, kk },
+        [0x4, x, ..] => SkipIfNe { x, kk },
+        [0x5, x, y, 0x0] => SkipIfRegEq { x, y },
+        [0x5, x, y, 0x2] => SaveRange { x, y },
+        [0x5, x, y, 0x3] => LoadRange { x, y },
+        [0x6, x, ..] => SetReg { x, kk },
+        [0x7, x, ..] => AddReg { x, kk },
+        [0x8, x, y, 0x0] => Copy { x, y },
+        [0x8, x, y, 0x1] => Or { x, y },
+        [0x8, x, y, 0x2] => And { x, y },
+        [0x8, x, y, 0x3] => Xor { x, y },
+        [0x8, x, y, 0x4] => Add { x, y },
+        [0x8, x, y, 0x5] => Sub { x, y },
+        [0x8, x, y, 0x6] => ShiftRight { x, y },
+        [0x8, x, y, 0x7] => SubReversed { x, y },
+        [0x8, x, y, 0xE] => ShiftLeft { x, y },
+        [0x9, x, y, 0x0] => SkipIfRegNe { x, y },
+        [0xA, ..] => SetI(nnn),
+        [0xB, ..] => JumpOffset(nnn),
+        [0xC, x, ..] => Random { x, kk },
+        [0xD, x, y, n] => Draw { x, y, n },
+        [0xE, x, 0x9, 0xE] => SkipIfKey { x },
+        [0xE, x, 0xA, 0x1] => SkipIfNotKey { x },
+        [0xF, 0x0, 0x0, 0x0] => SetILong,
+        [0xF, n, 0x0, 0x1] => SelectPlanes { n },
+        [0xF, x, 0x0, 0x7] => GetDelay { x },
+        [0xF, x, 0x0, 0xA] => WaitKey { x },
+        [0xF, x, 0x1, 0x5] => SetDelay { x },
+        [0xF, x, 0x1, 0x8] => SetSound { x },
+        [0xF, x, 0x1, 0xE] => AddI { x },
+        [0xF, x, 0x2, 0x9] => FontChar { x },
+        [0xF, x, 0x3, 0x3] => Bcd { x },
+        [0xF, x, 0x5, 0x5] => Store { x },
+        [0xF, x, 0x6, 0x5] => Load { x },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+impl Instruction {
+    /// Returns whether the instruction only exists on XO-CHIP.
+    pub fn is_xochip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::SaveRange { .. }
+                | Instruction::LoadRange { .. }
+                | Instruction::SetILong
+                | Instruction::SelectPlanes { .. }
+        )
+    }
+
+    /// Returns the opcode pattern of the instruction, e.g. `8xy4` or `Fx07`.
+    pub fn pattern(&self) -> &'static str {
+        use Instruction::*;
+
+        match self {
+            ClearScreen => "00E0",
+            Return => "00EE",
+            MachineRoutine(_) => "0nnn",
+            Jump(_) => "1nnn",
+            Call(_) => "2nnn",
+            SkipIfEq { .. } => "3xkk",
+            SkipIfNe { .. } => "4xkk",
+            SkipIfRegEq { .. } => "5xy0",
+            SaveRange { .. } => "5xy2",
+            LoadRange { .. } => "5xy3",
+            SetReg { .. } => "6xkk",
+            AddReg { .. } => "7xkk",
+            Copy { .. } => "8xy0",
+            Or { .. } => "8xy1",
+            And { .. } => "8xy2",
+            Xor { .. } => "8xy3",
+            Add { .. } => "8xy4",
+            Sub { .. } => "8xy5",
+            ShiftRight { .. } => "8xy6",
+            SubReversed { .. } => "8xy7",
+            ShiftLeft { .. } => "8xyE",
+            SkipIfRegNe { .. } => "9xy0",
+            SetI(_) => "Annn",
+            JumpOffset(_) => "Bnnn",
+            Random { .. } => "Cxkk",
+            Draw { .. } => "Dxyn",
+            SkipIfKey { .. } => "Ex9E",
+            SkipIfNotKey { .. } => "ExA1",
+            SetILong => "F000",
+            SelectPlanes { .. } => "Fn01",
+            GetDelay { .. } => "Fx07",
+            WaitKey { .. } => "Fx0A",
+            SetDelay { .. } => "Fx15",
+            SetSound { .. } => "Fx18",
+            AddI { .. } => "Fx1E",
+            FontChar { .. } => "Fx29",
+            Bcd { .. } => "Fx33",
+            Store { .. } => "Fx55",
+            Load { .. } => "Fx65",
+        }
+    }
+}
@@ -1,12 +1,66 @@
 use super::{
+    decode::{decode, Instruction},
     instructions::{self as instr, ControlFlow},
     Interpreter,
 };
+use crate::stack::{StackOverflow, StackUnderflow};
 use std::{
-    fmt::Debug,
+    error::Error,
+    fmt::{self, Debug, Display},
     ops::{BitOr, Shl},
 };
 
+/// An error that occurred while executing an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecuteError {
+    /// The opcode does not decode to any known instruction.
+    UnknownOpcode(u16),
+    /// A subroutine was called with 16 return addresses already on the stack.
+    StackOverflow,
+    /// A subroutine returned with no return address on the stack.
+    StackUnderflow,
+    /// An instruction accessed memory past the last address.
+    MemoryOutOfBounds(usize),
+    /// A jump or call targeted an odd address. Only reported in strict mode.
+    UnalignedJump(u16),
+    /// The program counter points past the last complete instruction in memory, usually because
+    /// the program ran off the end of its code.
+    PcOutOfRange(usize),
+}
+
+impl From<StackOverflow> for ExecuteError {
+    fn from(_: StackOverflow) -> Self {
+        ExecuteError::StackOverflow
+    }
+}
+
+impl From<StackUnderflow> for ExecuteError {
+    fn from(_: StackUnderflow) -> Self {
+        ExecuteError::StackUnderflow
+    }
+}
+
+impl Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecuteError::UnknownOpcode(opcode) => write!(f, "unknown opcode: {:04X}", opcode),
+            ExecuteError::StackOverflow => write!(f, "stack overflow"),
+            ExecuteError::StackUnderflow => write!(f, "stack underflow: return without call"),
+            ExecuteError::MemoryOutOfBounds(address) => {
+                write!(f, "memory access out of bounds: {:04X}", address)
+            }
+            ExecuteError::UnalignedJump(address) => {
+                write!(f, "jump to unaligned address: {:04X}", address)
+            }
+            ExecuteError::PcOutOfRange(address) => {
+                write!(f, "program counter out of range: {:04X}", address)
+            }
+        }
+    }
+}
+
+impl Error for ExecuteError {}
+
 /// An opcode for decoding a CHIP-8 instruction.
 pub struct Opcode {
     /// Stores the opcode's nibbles, starting at the highest 4 bits.
@@ -17,7 +71,7 @@ pub struct Opcode {
 }
 
 impl Opcode {
-    fn new(upper_byte: u8, lower_byte: u8) -> Self {
+    pub(crate) fn new(upper_byte: u8, lower_byte: u8) -> Self {
         let nibbles = [
             upper_byte >> 4,
             upper_byte & 0x0F,
@@ -27,6 +81,11 @@ impl Opcode {
 
         Opcode { nibbles }
     }
+
+    /// Returns the opcode as a single 16-bit value.
+    pub fn value(&self) -> u16 {
+        combine_nibbles(self.nibbles)
+    }
 }
 
 impl Debug for Opcode {
@@ -39,7 +98,10 @@ impl Debug for Opcode {
 }
 
 impl Interpreter {
-    pub fn fetch(&mut self) -> Opcode {
+    pub(crate) fn fetch(&mut self) -> Result<Opcode, ExecuteError> {
+        if self.program_counter + 1 >= self.memory.size() {
+            return Err(ExecuteError::PcOutOfRange(self.program_counter));
+        }
         let opcode = Opcode::new(
             self.memory.read_byte(self.program_counter),
             self.memory.read_byte(self.program_counter + 1),
@@ -47,125 +109,89 @@ impl Interpreter {
 
         self.program_counter += 2;
 
-        opcode
+        Ok(opcode)
+    }
+
+    /// Returns the opcode at `address` without executing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `address` is the last address of memory or past it.
+    pub fn opcode_at(&self, address: usize) -> u16 {
+        Opcode::new(
+            self.memory.read_byte(address),
+            self.memory.read_byte(address + 1),
+        )
+        .value()
     }
 }
 
 impl Interpreter {
-    pub fn execute(&mut self, opcode: Opcode) {
-        let p = self;
-        let control_flow = match opcode.nibbles {
-            // 00E0
-            [0x0, 0x0, 0xE, 0x0] => instr::instr_00E0(p),
-
-            // 00EE
-            [0x0, 0x0, 0xE, 0xE] => instr::instr_00EE(p),
-
-            // 1nnn
-            [0x1, nnn @ ..] => instr::instr_1nnn(p, combine_nibbles(nnn)),
-
-            // 2nnn
-            [0x2, nnn @ ..] => instr::instr_2nnn(p, combine_nibbles(nnn)),
-
-            // 3xkk
-            [0x3, x, kk @ ..] => instr::instr_3xkk(p, x, combine_nibbles(kk)),
-
-            // 4xkk
-            [0x4, x, kk @ ..] => instr::instr_4xkk(p, x, combine_nibbles(kk)),
-
-            // 5xy0
-            [0x5, x, y, 0x0] => instr::instr_5xy0(p, x, y),
-
-            // 6xkk
-            [0x6, x, kk @ ..] => instr::instr_6xkk(p, x, combine_nibbles(kk)),
-
-            // 7xkk
-            [0x7, x, kk @ ..] => instr::instr_7xkk(p, x, combine_nibbles(kk)),
+    pub(crate) fn execute(&mut self, opcode: Opcode) -> Result<ControlFlow, ExecuteError> {
+        use Instruction::*;
 
-            // 8xy0
-            [0x8, x, y, 0x0] => instr::instr_8xy0(p, x, y),
-
-            // 8xy1
-            [0x8, x, y, 0x1] => instr::instr_8xy1(p, x, y),
-
-            // 8xy2
-            [0x8, x, y, 0x2] => instr::instr_8xy2(p, x, y),
-
-            // 8xy3
-            [0x8, x, y, 0x3] => instr::instr_8xy3(p, x, y),
-
-            // 8xy4
-            [0x8, x, y, 0x4] => instr::instr_8xy4(p, x, y),
-
-            // 8xy5
-            [0x8, x, y, 0x5] => instr::instr_8xy5(p, x, y),
-
-            // 8xy6
-            [0x8, x, y, 0x6] => instr::instr_8xy6(p, x, y),
-
-            // 8xy7
-            [0x8, x, y, 0x7] => instr::instr_8xy7(p, x, y),
-
-            // 8xyE
-            [0x8, x, y, 0xE] => instr::instr_8xyE(p, x, y),
-
-            // 9xy0
-            [0x9, x, y, 0x0] => instr::instr_9xy0(p, x, y),
-
-            // Annn
-            [0xA, nnn @ ..] => instr::instr_Annn(p, combine_nibbles(nnn)),
-
-            // Bnnn
-            [0xB, nnn @ ..] => instr::instr_Bnnn(p, combine_nibbles(nnn)),
-
-            // Cxkk
-            [0xC, x, kk @ ..] => instr::instr_Cxkk(p, x, combine_nibbles(kk)),
-
-            // Dxyn
-            [0xD, x, y, n] => instr::instr_Dxyn(p, x, y, n),
-
-            // Ex9E
-            [0xE, x, 0x9, 0xE] => instr::instr_Ex9E(p, x),
-
-            // ExA1
-            [0xE, x, 0xA, 0x1] => instr::instr_ExA1(p, x),
-
-            // Fx07
-            [0xF, x, 0x0, 0x7] => instr::instr_Fx07(p, x),
-
-            // Fx0A
-            [0xF, x, 0x0, 0xA] => instr::instr_Fx0A(p, x),
-
-            // Fx15
-            [0xF, x, 0x1, 0x5] => instr::instr_Fx15(p, x),
-
-            // Fx18
-            [0xF, x, 0x1, 0x8] => instr::instr_Fx18(p, x),
-
-            // Fx1E
-            [0xF, x, 0x1, 0xE] => instr::instr_Fx1E(p, x),
-
-            // Fx29
-            [0xF, x, 0x2, 0x9] => instr::instr_Fx29(p, x),
-
-            // Fx33
-            [0xF, x, 0x3, 0x3] => instr::instr_Fx33(p, x),
-
-            // Fx55
-            [0xF, x, 0x5, 0x5] => instr::instr_Fx55(p, x),
-
-            // Fx65
-            [0xF, x, 0x6, 0x5] => instr::instr_Fx65(p, x),
-
-            _ => panic!("invalid opcode: {:?}", opcode),
+        let p = self;
+        let instruction = match decode(opcode.value()) {
+            Some(instruction) if !instruction.is_xochip() || p.quirks.xochip_opcodes => instruction,
+            _ => return Err(ExecuteError::UnknownOpcode(opcode.value())),
+        };
+        let control_flow = match instruction {
+            ClearScreen => instr::instr_00E0(p),
+            Return => instr::instr_00EE(p)?,
+            MachineRoutine(nnn) => instr::instr_0nnn(p, nnn),
+            Jump(nnn) => instr::instr_1nnn(p, nnn)?,
+            Call(nnn) => instr::instr_2nnn(p, nnn)?,
+            SkipIfEq { x, kk } => instr::instr_3xkk(p, x, kk),
+            SkipIfNe { x, kk } => instr::instr_4xkk(p, x, kk),
+            SkipIfRegEq { x, y } => instr::instr_5xy0(p, x, y),
+            SaveRange { x, y } => instr::instr_5xy2(p, x, y)?,
+            LoadRange { x, y } => instr::instr_5xy3(p, x, y)?,
+            SetReg { x, kk } => instr::instr_6xkk(p, x, kk),
+            AddReg { x, kk } => instr::instr_7xkk(p, x, kk),
+            Copy { x, y } => instr::instr_8xy0(p, x, y),
+            Or { x, y } => instr::instr_8xy1(p, x, y),
+            And { x, y } => instr::instr_8xy2(p, x, y),
+            Xor { x, y } => instr::instr_8xy3(p, x, y),
+            Add { x, y } => instr::instr_8xy4(p, x, y),
+            Sub { x, y } => instr::instr_8xy5(p, x, y),
+            ShiftRight { x, y } => instr::instr_8xy6(p, x, y),
+            SubReversed { x, y } => instr::instr_8xy7(p, x, y),
+            ShiftLeft { x, y } => instr::instr_8xyE(p, x, y),
+            SkipIfRegNe { x, y } => instr::instr_9xy0(p, x, y),
+            SetI(nnn) => instr::instr_Annn(p, nnn),
+            JumpOffset(nnn) => instr::instr_Bnnn(p, nnn)?,
+            Random { x, kk } => instr::instr_Cxkk(p, x, kk),
+            Draw { x, y, n } => instr::instr_Dxyn(p, x, y, n)?,
+            SkipIfKey { x } => instr::instr_Ex9E(p, x),
+            SkipIfNotKey { x } => instr::instr_ExA1(p, x),
+            SetILong => instr::instr_F000(p)?,
+            SelectPlanes { n } => instr::instr_Fn01(p, n),
+            GetDelay { x } => instr::instr_Fx07(p, x),
+            WaitKey { x } => instr::instr_Fx0A(p, x),
+            SetDelay { x } => instr::instr_Fx15(p, x),
+            SetSound { x } => instr::instr_Fx18(p, x),
+            AddI { x } => instr::instr_Fx1E(p, x),
+            FontChar { x } => instr::instr_Fx29(p, x),
+            Bcd { x } => instr::instr_Fx33(p, x)?,
+            Store { x } => instr::instr_Fx55(p, x)?,
+            Load { x } => instr::instr_Fx65(p, x)?,
         };
 
         match control_flow {
             ControlFlow::Wait => p.program_counter -= 2,
-            ControlFlow::Skip => p.program_counter += 2,
+            ControlFlow::Skip => {
+                // XO-CHIP's F000 nnnn is the only instruction that is four bytes long
+                let long = p.quirks.xochip_opcodes
+                    && p.program_counter + 1 < p.memory.size()
+                    && p.opcode_at(p.program_counter) == 0xF000;
+                p.program_counter += if long { 4 } else { 2 };
+            }
             ControlFlow::Jump(loc) => p.program_counter = loc as usize,
+            ControlFlow::DisplayWait => p.display_wait_tick = Some(p.timers.ticks()),
             ControlFlow::None => (),
         }
+
+        Ok(control_flow)
     }
 }
 
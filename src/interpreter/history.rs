@@ -0,0 +1,121 @@
+use super::{Interpreter, InterpreterState};
+
+impl Interpreter {
+    /// Keeps the machine states from before the last `capacity` steps, so they can be undone
+    /// with [`Interpreter::step_back`]. A capacity of 0, the default, turns the history off.
+    ///
+    /// Every state includes all of memory, so a large history costs both memory and time on
+    /// every step, especially with XO-CHIP's 64K memory.
+    pub fn set_step_history(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// Undoes the last step by restoring the state from before it, see
+    /// [`Interpreter::set_step_history`].
+    ///
+    /// Returns `false` if there is no step left to undo.
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(state) => {
+                self.restore_unchecked(&state);
+                self.cycle_count = self.cycle_count.saturating_sub(1);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keeps the machine states of the last `frames` 60 Hz frames, so they can be scrubbed
+    /// through with [`Interpreter::rewind`] and [`Interpreter::fast_forward`]. A capacity of 0,
+    /// the default, turns the history off.
+    ///
+    /// A state is kept every time the timers count down, which is much cheaper than
+    /// [`Interpreter::set_step_history`] for the same stretch of time.
+    pub fn set_rewind_history(&mut self, frames: usize) {
+        self.frame_history_capacity = frames;
+        self.discard_rewound_frames();
+        while self.frame_history.len() > frames {
+            self.frame_history.pop_front();
+        }
+    }
+
+    /// Goes back `frames` frames and pauses, see [`Interpreter::set_rewind_history`]. Stops at
+    /// the oldest frame kept.
+    ///
+    /// The frames rewound over can be returned to with [`Interpreter::fast_forward`] until the
+    /// interpreter steps again, which starts a new timeline from the rewound state.
+    ///
+    /// Returns `false` if there is no frame to go back to.
+    pub fn rewind(&mut self, frames: usize) -> bool {
+        let position = match self.rewind_position {
+            Some(position) => position,
+            None if self.frame_history_capacity > 0 => {
+                // Keep the current state, so fast-forwarding can return to it, unless it's the
+                // latest frame already
+                let latest = self.frame_history.back().map(|&(_, cycles)| cycles);
+                if latest != Some(self.cycle_count) {
+                    self.record_frame();
+                }
+                self.frame_history.len() - 1
+            }
+            None => return false,
+        };
+        if position == 0 {
+            return false;
+        }
+        self.go_to_frame(position.saturating_sub(frames));
+        true
+    }
+
+    /// Goes forward `frames` frames through the frames undone with [`Interpreter::rewind`], and
+    /// stays paused.
+    ///
+    /// Returns `false` if there is no rewound frame to go forward to.
+    pub fn fast_forward(&mut self, frames: usize) -> bool {
+        match self.rewind_position {
+            Some(position) if position + 1 < self.frame_history.len() => {
+                self.go_to_frame((position + frames).min(self.frame_history.len() - 1));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn go_to_frame(&mut self, position: usize) {
+        let (state, cycle_count) = self.frame_history[position].clone();
+        self.restore_unchecked(&state);
+        self.cycle_count = cycle_count;
+        self.rewind_position = Some(position);
+        self.paused = true;
+    }
+
+    pub(super) fn record_history(&mut self, state: InterpreterState) {
+        if self.history.len() == self.history_capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(state);
+    }
+
+    /// Keeps the current state as the latest frame, if the rewind history is on.
+    pub(super) fn record_frame(&mut self) {
+        if self.frame_history_capacity == 0 {
+            return;
+        }
+        self.discard_rewound_frames();
+        if self.frame_history.len() == self.frame_history_capacity {
+            self.frame_history.pop_front();
+        }
+        self.frame_history
+            .push_back((self.snapshot(), self.cycle_count));
+    }
+
+    /// Forgets the frames after the one rewound to, since the timeline continues from it.
+    pub(super) fn discard_rewound_frames(&mut self) {
+        if let Some(position) = self.rewind_position.take() {
+            self.frame_history.truncate(position + 1);
+        }
+    }
+}
@@ -1,32 +1,74 @@
 #![allow(non_snake_case)]
 
-use super::Interpreter;
+use super::{DrawEvent, ExecuteError, Interpreter};
+use crate::display::DisplayBuffer;
+use rand::Rng;
+use std::borrow::Cow;
 
+/// How an instruction affects the program counter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ControlFlow {
+    /// Execute the same instruction again.
     Wait,
+    /// Skip the next instruction.
     Skip,
+    /// Continue at the given address.
     Jump(u16),
+    /// Continue with the next instruction.
     None,
+    /// Continue with the next instruction, but not before the next 60 Hz frame.
+    DisplayWait,
 }
 
 pub fn instr_00E0(interp: &mut Interpreter) -> ControlFlow {
     interp.display_buf.clear();
+    if let Some(handler) = &mut interp.draw_handler {
+        handler(DrawEvent::Clear);
+    }
     ControlFlow::None
 }
 
-pub fn instr_00EE(interp: &mut Interpreter) -> ControlFlow {
-    let pc = interp.stack.pop().unwrap();
-    ControlFlow::Jump(pc)
+pub fn instr_00EE(interp: &mut Interpreter) -> Result<ControlFlow, ExecuteError> {
+    let pc = interp.stack.pop()?;
+    Ok(ControlFlow::Jump(pc))
 }
 
-pub fn instr_1nnn(_interp: &mut Interpreter, nnn: u16) -> ControlFlow {
-    ControlFlow::Jump(nnn)
+pub fn instr_0nnn(interp: &mut Interpreter, nnn: u16) -> ControlFlow {
+    // The patched interpreter of hires programs has a routine that clears the 64x64 display
+    if interp.quirks.vip_hires && nnn == 0x230 {
+        return instr_00E0(interp);
+    }
+    // Native machine code routines can't be executed, so only notify the handler
+    if let Some(handler) = &mut interp.machine_routine_handler {
+        handler(nnn);
+    }
+    ControlFlow::None
 }
 
-pub fn instr_2nnn(interp: &mut Interpreter, nnn: u16) -> ControlFlow {
-    // Return from subroutine at next instruction
-    interp.stack.push((interp.program_counter) as u16);
-    ControlFlow::Jump(nnn)
+/// Returns a jump to `address`, or an error if it's odd in strict mode.
+fn jump(interp: &Interpreter, address: u16) -> Result<ControlFlow, ExecuteError> {
+    if interp.strict && address & 1 == 1 {
+        return Err(ExecuteError::UnalignedJump(address));
+    }
+    Ok(ControlFlow::Jump(address))
+}
+
+pub fn instr_1nnn(interp: &mut Interpreter, nnn: u16) -> Result<ControlFlow, ExecuteError> {
+    // Hires programs jump from 0x200 into their copy of the interpreter, which sets up the 64x64
+    // display and runs the program at 0x2C0
+    if interp.quirks.vip_hires && nnn == 0x260 && interp.program_counter == 0x202 {
+        interp.display_buf.set_hires(true);
+        return Ok(ControlFlow::Jump(0x2C0));
+    }
+    jump(interp, nnn)
+}
+
+pub fn instr_2nnn(interp: &mut Interpreter, nnn: u16) -> Result<ControlFlow, ExecuteError> {
+    let control_flow = jump(interp, nnn)?;
+    // `fetch` has already moved the program counter past the call, so the subroutine returns to
+    // the next instruction
+    interp.stack.push(interp.program_counter as u16)?;
+    Ok(control_flow)
 }
 
 pub fn instr_3xkk(interp: &mut Interpreter, x: u8, kk: u8) -> ControlFlow {
@@ -53,6 +95,43 @@ pub fn instr_5xy0(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     }
 }
 
+/// Returns the registers from `x` to `y`, in descending order if `x` is larger than `y`.
+fn register_range(x: u8, y: u8) -> Vec<usize> {
+    if x <= y {
+        (x..=y).map(usize::from).collect()
+    } else {
+        (y..=x).rev().map(usize::from).collect()
+    }
+}
+
+/// Returns `I` if the `len` bytes starting at it are in memory, for the instructions that save or
+/// load a range of registers.
+fn register_memory_start(interp: &Interpreter, len: usize) -> Result<usize, ExecuteError> {
+    let i = interp.reg_i as usize;
+    if i + len > interp.memory.size() {
+        return Err(ExecuteError::MemoryOutOfBounds(i.max(interp.memory.size())));
+    }
+    Ok(i)
+}
+
+pub fn instr_5xy2(interp: &mut Interpreter, x: u8, y: u8) -> Result<ControlFlow, ExecuteError> {
+    let registers = register_range(x, y);
+    let i = register_memory_start(interp, registers.len())?;
+    for (offset, register) in registers.into_iter().enumerate() {
+        interp.write_memory(i + offset, interp.reg_v[register]);
+    }
+    Ok(ControlFlow::None)
+}
+
+pub fn instr_5xy3(interp: &mut Interpreter, x: u8, y: u8) -> Result<ControlFlow, ExecuteError> {
+    let registers = register_range(x, y);
+    let i = register_memory_start(interp, registers.len())?;
+    for (offset, register) in registers.into_iter().enumerate() {
+        interp.reg_v[register] = interp.memory.read_byte(i + offset);
+    }
+    Ok(ControlFlow::None)
+}
+
 pub fn instr_6xkk(interp: &mut Interpreter, x: u8, kk: u8) -> ControlFlow {
     *interp.reg_v_mut(x) = kk;
     ControlFlow::None
@@ -70,26 +149,37 @@ pub fn instr_8xy0(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
 
 pub fn instr_8xy1(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     *interp.reg_v_mut(x) = interp.reg_v(x) | interp.reg_v(y);
+    reset_vf_after_logic(interp);
     ControlFlow::None
 }
 
 pub fn instr_8xy2(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     *interp.reg_v_mut(x) = interp.reg_v(x) & interp.reg_v(y);
+    reset_vf_after_logic(interp);
     ControlFlow::None
 }
 
 pub fn instr_8xy3(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     *interp.reg_v_mut(x) = interp.reg_v(x) ^ interp.reg_v(y);
+    reset_vf_after_logic(interp);
     ControlFlow::None
 }
 
+fn reset_vf_after_logic(interp: &mut Interpreter) {
+    if interp.quirks.logic_resets_vf {
+        *interp.reg_v_mut(0xF) = 0;
+    }
+}
+
+/// Like the other arithmetic instructions that set `VF` (`8xy5`, `8xy6`, `8xy7` and `8xyE`),
+/// reads both operands first and writes `VF` after `Vx`. When `x` is 0xF, `VF` ends up holding
+/// the flag and the result is lost, as on the COSMAC VIP.
 pub fn instr_8xy4(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     let v_y = interp.reg_v(y);
     let v_x = interp.reg_v_mut(x);
     let (sum, overflowed) = v_x.overflowing_add(v_y);
     *v_x = sum;
     interp.reg_v[0xF] = overflowed as u8;
-    // println!("{:?}", (interp.reg_v(x), interp.reg_v(y)));
     ControlFlow::None
 }
 
@@ -104,7 +194,11 @@ pub fn instr_8xy5(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
 
 pub fn instr_8xy6(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
     let x = x as usize;
-    let y = y as usize;
+    let y = if interp.quirks.shift_uses_vx {
+        x
+    } else {
+        y as usize
+    };
     let v_y = interp.reg_v[y];
     let lsb = v_y & 0x1;
     interp.reg_v[x] = v_y >> 1;
@@ -122,6 +216,7 @@ pub fn instr_8xy7(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
 }
 
 pub fn instr_8xyE(interp: &mut Interpreter, x: u8, y: u8) -> ControlFlow {
+    let y = if interp.quirks.shift_uses_vx { x } else { y };
     let v_y = interp.reg_v(y);
     let msb = v_y >> 7;
     *interp.reg_v_mut(x) = v_y << 1;
@@ -142,31 +237,70 @@ pub fn instr_Annn(interp: &mut Interpreter, nnn: u16) -> ControlFlow {
     ControlFlow::None
 }
 
-pub fn instr_Bnnn(interp: &mut Interpreter, nnn: u16) -> ControlFlow {
-    let loc = nnn + interp.reg_v[0x0] as u16;
-    ControlFlow::Jump(loc)
+pub fn instr_Bnnn(interp: &mut Interpreter, nnn: u16) -> Result<ControlFlow, ExecuteError> {
+    let x = if interp.quirks.jump_uses_vx {
+        (nnn >> 8) as u8
+    } else {
+        0x0
+    };
+    let loc = nnn + interp.reg_v(x) as u16;
+    jump(interp, loc)
 }
 
 pub fn instr_Cxkk(interp: &mut Interpreter, x: u8, kk: u8) -> ControlFlow {
-    let rand_byte = rand::random::<u8>();
+    let rand_byte = interp.rng.gen::<u8>();
     *interp.reg_v_mut(x) = rand_byte & kk;
     ControlFlow::None
 }
 
-pub fn instr_Dxyn(interp: &mut Interpreter, x: u8, y: u8, n: u8) -> ControlFlow {
+pub fn instr_Dxyn(
+    interp: &mut Interpreter,
+    x: u8,
+    y: u8,
+    n: u8,
+) -> Result<ControlFlow, ExecuteError> {
     let p = interp;
-    let x_pos = p.reg_v(x) as usize;
-    let y_pos = p.reg_v(y) as usize;
-
-    if x_pos > 0x3F || y_pos > 0x1F {
-        p.reg_v[0xF] = 0;
-        return ControlFlow::None;
+    // The starting position always wraps, only the rest of the sprite can be clipped
+    let x_pos = p.reg_v(x) as usize % DisplayBuffer::WIDTH;
+    let height = p.display_buf.height();
+    let y_pos = p.reg_v(y) as usize % height;
+
+    // Each selected plane gets its own n rows of sprite data
+    let planes = p.display_buf.selected_planes().count_ones() as usize;
+    let (i, len) = (p.reg_i as usize, n as usize * planes);
+    let size = p.memory.size();
+    let sprite = if i + len <= size {
+        Cow::Borrowed(p.memory.read_sprite(i, len))
+    } else if p.strict {
+        return Err(ExecuteError::MemoryOutOfBounds(i.max(size)));
+    } else {
+        Cow::Owned(
+            (i..i + len)
+                .map(|address| p.memory.read_byte(address % size))
+                .collect(),
+        )
+    };
+    let collided_rows = p
+        .display_buf
+        .write_sprite(&sprite, x_pos, y_pos, !p.quirks.clip_sprites);
+    p.reg_v[0xF] = if p.quirks.collision_counts_rows {
+        collided_rows
+    } else {
+        (collided_rows > 0) as u8
+    };
+    if let Some(handler) = &mut p.draw_handler {
+        handler(DrawEvent::Sprite {
+            x: x_pos,
+            y: y_pos,
+            height: n,
+            collision: collided_rows > 0,
+        });
     }
-
-    let sprite = p.memory.read_sprite(p.reg_i as usize, n as usize);
-    let collision = p.display_buf.write_sprite(sprite, x_pos, y_pos);
-    p.reg_v[0xF] = collision as u8;
-    ControlFlow::None
+    Ok(if p.quirks.display_wait {
+        ControlFlow::DisplayWait
+    } else {
+        ControlFlow::None
+    })
 }
 
 pub fn instr_Ex9E(interp: &mut Interpreter, x: u8) -> ControlFlow {
@@ -193,8 +327,9 @@ pub fn instr_Fx07(interp: &mut Interpreter, x: u8) -> ControlFlow {
 }
 
 pub fn instr_Fx0A(interp: &mut Interpreter, x: u8) -> ControlFlow {
-    if let Some(key) = interp.keyboard_state.any_pressed() {
-        *interp.reg_v_mut(x) = key as u8;
+    // The key is registered on release, not on press
+    if let Some(key) = interp.keyboard_state.wait_for_release() {
+        *interp.reg_v_mut(x) = key.hex();
         ControlFlow::None
     } else {
         ControlFlow::Wait
@@ -212,7 +347,30 @@ pub fn instr_Fx18(interp: &mut Interpreter, x: u8) -> ControlFlow {
 }
 
 pub fn instr_Fx1E(interp: &mut Interpreter, x: u8) -> ControlFlow {
-    interp.reg_i += interp.reg_v(x) as u16;
+    let sum = interp.reg_i as usize + interp.reg_v(x) as usize;
+    if interp.quirks.i_overflow_sets_vf {
+        interp.reg_v[0xF] = (sum > 0x0FFF) as u8;
+    }
+    // Wrap around within memory, 12 bits normally and 16 bits with XO-CHIP's 64 KiB
+    interp.reg_i = (sum % interp.memory.size()) as u16;
+    ControlFlow::None
+}
+
+pub fn instr_F000(interp: &mut Interpreter) -> Result<ControlFlow, ExecuteError> {
+    // The address is in the two bytes following the opcode
+    let pc = interp.program_counter;
+    if pc + 1 >= interp.memory.size() {
+        return Err(ExecuteError::MemoryOutOfBounds(pc + 1));
+    }
+    let upper = interp.memory.read_byte(pc) as u16;
+    let lower = interp.memory.read_byte(pc + 1) as u16;
+    interp.reg_i = upper << 8 | lower;
+    // The address can be the last two bytes of memory, so the next instruction wraps around
+    Ok(ControlFlow::Jump(((pc + 2) % interp.memory.size()) as u16))
+}
+
+pub fn instr_Fn01(interp: &mut Interpreter, n: u8) -> ControlFlow {
+    interp.display_buf.select_planes(n);
     ControlFlow::None
 }
 
@@ -221,29 +379,44 @@ pub fn instr_Fx29(interp: &mut Interpreter, x: u8) -> ControlFlow {
     ControlFlow::None
 }
 
-pub fn instr_Fx33(interp: &mut Interpreter, x: u8) -> ControlFlow {
+pub fn instr_Fx33(interp: &mut Interpreter, x: u8) -> Result<ControlFlow, ExecuteError> {
     let value = interp.reg_v(x);
     let i = interp.reg_i as usize;
-    interp.memory.write_byte(i, value / 100);
-    interp.memory.write_byte(i + 1, value % 100 / 10);
-    interp.memory.write_byte(i + 2, value % 10);
-    ControlFlow::None
+    if i + 2 >= interp.memory.size() {
+        return Err(ExecuteError::MemoryOutOfBounds(i + 2));
+    }
+    interp.write_memory(i, value / 100);
+    interp.write_memory(i + 1, value % 100 / 10);
+    interp.write_memory(i + 2, value % 10);
+    Ok(ControlFlow::None)
 }
 
-pub fn instr_Fx55(interp: &mut Interpreter, x: u8) -> ControlFlow {
+pub fn instr_Fx55(interp: &mut Interpreter, x: u8) -> Result<ControlFlow, ExecuteError> {
     let x = x as usize;
-    let i = interp.reg_i as usize;
+    let i = register_memory_start(interp, x + 1)?;
     for offset in 0..=x {
-        interp.memory.write_byte(i + offset, interp.reg_v[offset]);
+        interp.write_memory(i + offset, interp.reg_v[offset]);
     }
-    ControlFlow::None
+    increment_i_after_load_store(interp, x);
+    Ok(ControlFlow::None)
 }
 
-pub fn instr_Fx65(interp: &mut Interpreter, x: u8) -> ControlFlow {
+pub fn instr_Fx65(interp: &mut Interpreter, x: u8) -> Result<ControlFlow, ExecuteError> {
     let x = x as usize;
-    let i = interp.reg_i as usize;
+    let i = register_memory_start(interp, x + 1)?;
     for offset in 0..=x {
         interp.reg_v[offset] = interp.memory.read_byte(i + offset);
     }
-    ControlFlow::None
+    increment_i_after_load_store(interp, x);
+    Ok(ControlFlow::None)
+}
+
+/// Moves `I` past the registers `Fx55` and `Fx65` stored or loaded, if
+/// [`Quirks::load_store_increments_i`](crate::Quirks::load_store_increments_i) is enabled.
+///
+/// Wraps around, since the range can end at the very top of XO-CHIP's 64K memory.
+fn increment_i_after_load_store(interp: &mut Interpreter, x: usize) {
+    if interp.quirks.load_store_increments_i {
+        interp.reg_i = interp.reg_i.wrapping_add(x as u16 + 1);
+    }
 }
@@ -0,0 +1,33 @@
+use super::{decode::decode, Interpreter, Opcode};
+
+impl Interpreter {
+    /// Enables or disables counting how often each kind of instruction is executed.
+    pub fn set_profiling_enabled(&mut self, enabled: bool) {
+        self.profiling_enabled = enabled;
+    }
+
+    /// Returns how often each kind of instruction has been executed while profiling was enabled,
+    /// most frequent first.
+    ///
+    /// Instructions are named by their opcode pattern, e.g. `8xy4` or `Fx07`.
+    pub fn opcode_stats(&self) -> Vec<(&'static str, u64)> {
+        let mut stats: Vec<_> = self
+            .opcode_counts
+            .iter()
+            .map(|(&pattern, &count)| (pattern, count))
+            .collect();
+        stats.sort_by(|(a_pattern, a_count), (b_pattern, b_count)| {
+            b_count.cmp(a_count).then(a_pattern.cmp(b_pattern))
+        });
+        stats
+    }
+
+    pub fn clear_opcode_stats(&mut self) {
+        self.opcode_counts.clear();
+    }
+
+    pub(super) fn record_opcode(&mut self, opcode: &Opcode) {
+        let pattern = decode(opcode.value()).map_or("unknown", |instruction| instruction.pattern());
+        *self.opcode_counts.entry(pattern).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,156 @@
+use super::Interpreter;
+use crate::{
+    display::DisplayBuffer,
+    keyboard::KeyboardState,
+    memory::{Memory, FONT_SIZE},
+    stack::{Stack, STACK_SIZE},
+    timer::Timers,
+};
+#[cfg(feature = "serde")]
+use serde::{de::Error as _, Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::io::{Read, Write};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The error returned when restoring a state that doesn't fit the interpreter, e.g. from a
+/// corrupted save file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidState {
+    /// The state's memory doesn't have the size of the interpreter's memory.
+    MemorySize { size: usize, expected: usize },
+    /// The font would overlap the program area starting at 0x200.
+    FontAddress(usize),
+    /// The call stack holds more return addresses than the stack has room for.
+    StackDepth(usize),
+    /// The program counter is past the end of memory.
+    ProgramCounter(usize),
+}
+
+impl Display for InvalidState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvalidState::MemorySize { size, expected } => write!(
+                f,
+                "the state has {} bytes of memory, but the interpreter has {}",
+                size, expected
+            ),
+            InvalidState::FontAddress(address) => write!(
+                f,
+                "the font address {:04X} overlaps the program area",
+                address
+            ),
+            InvalidState::StackDepth(depth) => write!(
+                f,
+                "the stack holds {} return addresses, but at most {} fit",
+                depth, STACK_SIZE
+            ),
+            InvalidState::ProgramCounter(address) => {
+                write!(
+                    f,
+                    "the program counter {:04X} is past the end of memory",
+                    address
+                )
+            }
+        }
+    }
+}
+
+impl Error for InvalidState {}
+
+/// A snapshot of the complete machine state of an [`Interpreter`].
+///
+/// Created with [`Interpreter::snapshot`] and restored with [`Interpreter::restore`].
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InterpreterState {
+    memory: Memory,
+    display_buf: DisplayBuffer,
+    timers: Timers,
+    keyboard_state: KeyboardState,
+
+    stack: Stack,
+    program_counter: usize,
+    reg_i: u16,
+    reg_v: [u8; 16],
+}
+
+impl Interpreter {
+    /// Captures the current machine state.
+    pub fn snapshot(&self) -> InterpreterState {
+        InterpreterState {
+            memory: self.memory.clone(),
+            display_buf: self.display_buf.clone(),
+            timers: self.timers.clone(),
+            keyboard_state: self.keyboard_state.clone(),
+
+            stack: self.stack.clone(),
+            program_counter: self.program_counter,
+            reg_i: self.reg_i,
+            reg_v: self.reg_v,
+        }
+    }
+
+    /// Restores a machine state previously captured with [`Interpreter::snapshot`].
+    ///
+    /// Configuration such as quirks is not part of the state and is kept as is. The state is
+    /// checked against it first, and left unrestored if e.g. its memory has a different size.
+    pub fn restore(&mut self, state: &InterpreterState) -> Result<(), InvalidState> {
+        let size = state.memory.size();
+        if size != self.memory.size() {
+            return Err(InvalidState::MemorySize {
+                size,
+                expected: self.memory.size(),
+            });
+        }
+        let font_address = state.memory.font_address();
+        if font_address + FONT_SIZE > 0x200 {
+            return Err(InvalidState::FontAddress(font_address));
+        }
+        let depth = state.stack.frames().len();
+        if depth > STACK_SIZE {
+            return Err(InvalidState::StackDepth(depth));
+        }
+        if state.program_counter >= size {
+            return Err(InvalidState::ProgramCounter(state.program_counter));
+        }
+        self.restore_unchecked(state);
+        Ok(())
+    }
+
+    /// Restores a state captured from this interpreter, which needs no checks.
+    pub(super) fn restore_unchecked(&mut self, state: &InterpreterState) {
+        self.memory = state.memory.clone();
+        self.display_buf.replace(&state.display_buf);
+        self.timers = state.timers.clone();
+        self.timers.restart(self.clock.now());
+        self.keyboard_state = state.keyboard_state.clone();
+        self.key_releases.clear();
+
+        self.stack = state.stack.clone();
+        self.program_counter = state.program_counter;
+        self.reg_i = state.reg_i;
+        self.reg_v = state.reg_v;
+        self.display_wait_tick = None;
+        self.halted = false;
+        self.update_sound();
+    }
+
+    /// Writes the current machine state to `writer`, e.g. to create a save file.
+    #[cfg(feature = "serde")]
+    pub fn save_to_writer<W: Write>(&self, writer: W) -> bincode::Result<()> {
+        bincode::serialize_into(writer, &self.snapshot())
+    }
+
+    /// Reads a machine state written by [`Interpreter::save_to_writer`] and restores it.
+    ///
+    /// The timers start counting from the time of loading. A state that doesn't fit the
+    /// interpreter, see [`Interpreter::restore`], is reported as a deserialization error.
+    #[cfg(feature = "serde")]
+    pub fn load_from_reader<R: Read>(&mut self, reader: R) -> bincode::Result<()> {
+        let state: InterpreterState = bincode::deserialize_from(reader)?;
+        self.restore(&state).map_err(bincode::Error::custom)
+    }
+}
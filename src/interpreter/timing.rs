@@ -0,0 +1,74 @@
+use super::{decode, Instruction, Interpreter};
+
+/// How long instructions take to execute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timing {
+    /// Every instruction takes one cycle of the configured clock rate.
+    #[default]
+    Uniform,
+    /// Every instruction takes about as long as it did on the COSMAC VIP, e.g. `Dxyn` takes
+    /// far longer than `6xkk`. The configured clock rate is ignored.
+    Vip,
+}
+
+impl Interpreter {
+    /// Sets how long instructions take to execute. Defaults to [`Timing::Uniform`].
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Returns how many cycles of the configured clock rate the next instruction takes.
+    pub(super) fn next_instruction_cost(&self) -> f64 {
+        let pc = self.program_counter;
+        if self.timing == Timing::Uniform || pc + 1 >= self.memory.size() {
+            return 1.0;
+        }
+        let micros = decode(self.opcode_at(pc))
+            .map_or(VIP_UNKNOWN_MICROS, |instruction| vip_micros(&instruction));
+        micros as f64 / 1_000_000.0 / self.cycle_delay.as_secs_f64()
+    }
+}
+
+/// The time an unknown opcode takes on the COSMAC VIP, where it would call a machine code
+/// routine.
+const VIP_UNKNOWN_MICROS: u32 = 105;
+
+/// Returns the average time in microseconds `instruction` takes on the COSMAC VIP.
+///
+/// `Dxyn` includes waiting for the display interrupt, like the VIP's interpreter did. XO-CHIP's
+/// instructions didn't exist on the VIP and cost about as much as `Annn`.
+fn vip_micros(instruction: &Instruction) -> u32 {
+    use Instruction::*;
+
+    match instruction {
+        ClearScreen => 109,
+        Return | MachineRoutine(_) | Jump(_) | Call(_) | JumpOffset(_) => 105,
+        SkipIfEq { .. } | SkipIfNe { .. } => 55,
+        SkipIfRegEq { .. } | SkipIfRegNe { .. } => 73,
+        SetReg { .. } => 27,
+        AddReg { .. } => 45,
+        Copy { .. }
+        | Or { .. }
+        | And { .. }
+        | Xor { .. }
+        | Add { .. }
+        | Sub { .. }
+        | ShiftRight { .. }
+        | SubReversed { .. }
+        | ShiftLeft { .. } => 200,
+        SetI(_) => 55,
+        Random { .. } => 164,
+        Draw { .. } => 22_734,
+        SkipIfKey { .. } | SkipIfNotKey { .. } => 73,
+        GetDelay { .. } | WaitKey { .. } | SetDelay { .. } | SetSound { .. } => 45,
+        AddI { .. } => 86,
+        FontChar { .. } => 91,
+        Bcd { .. } => 927,
+        Store { .. } | Load { .. } => 605,
+        SaveRange { .. } | LoadRange { .. } | SetILong | SelectPlanes { .. } => 55,
+    }
+}
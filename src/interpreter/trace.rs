@@ -0,0 +1,41 @@
+use super::{decode, ControlFlow, Instruction, Interpreter};
+use std::collections::VecDeque;
+
+/// The number of entries kept in the trace before the oldest are discarded.
+const TRACE_CAPACITY: usize = 1024;
+
+/// A record of a single executed instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// The address of the instruction, i.e. the program counter before execution.
+    pub pc: usize,
+    pub opcode: u16,
+    /// How the instruction affected the program counter, or `None` if it failed to execute.
+    pub control_flow: Option<ControlFlow>,
+}
+
+impl TraceEntry {
+    /// Returns the instruction the opcode decodes to, or `None` if it's unknown.
+    pub fn instruction(&self) -> Option<Instruction> {
+        decode(self.opcode)
+    }
+}
+
+impl Interpreter {
+    /// Enables or disables recording of executed instructions.
+    pub fn set_trace_enabled(&mut self, enabled: bool) {
+        self.trace_enabled = enabled;
+    }
+
+    /// Returns the most recently executed instructions, oldest first.
+    pub fn trace(&self) -> &VecDeque<TraceEntry> {
+        &self.trace
+    }
+
+    pub(super) fn record_trace(&mut self, entry: TraceEntry) {
+        if self.trace.len() == TRACE_CAPACITY {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(entry);
+    }
+}
@@ -1,45 +1,125 @@
-use winit::event::VirtualKeyCode;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
+/// One of the 16 keys on the CHIP-8 hexadecimal keypad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[repr(u8)]
+pub enum Key {
+    Num0 = 0x0,
+    Num1 = 0x1,
+    Num2 = 0x2,
+    Num3 = 0x3,
+    Num4 = 0x4,
+    Num5 = 0x5,
+    Num6 = 0x6,
+    Num7 = 0x7,
+    Num8 = 0x8,
+    Num9 = 0x9,
+    A = 0xA,
+    B = 0xB,
+    C = 0xC,
+    D = 0xD,
+    E = 0xE,
+    F = 0xF,
+}
+
+impl Key {
+    /// All keys, ordered by their hexadecimal value.
+    pub const ALL: [Key; 16] = [
+        Key::Num0,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+    ];
+
+    /// Returns the key with the given hexadecimal value, or `None` if it's larger than 0xF.
+    pub fn from_hex(hex: u8) -> Option<Key> {
+        Key::ALL.get(hex as usize).copied()
+    }
+
+    /// Returns the hexadecimal value of the key.
+    pub fn hex(self) -> u8 {
+        self as u8
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct KeyboardState {
     pub key: [bool; 16],
+    /// Whether `wait_for_release` is waiting for a key to be released.
+    waiting: bool,
+    /// The key that was released while waiting. If several were, the most recently pressed one.
+    released: Option<Key>,
+    /// For each key, the value of `presses` when it was last pressed.
+    pressed_at: [u64; 16],
+    presses: u64,
 }
 
 impl KeyboardState {
     pub fn new() -> Self {
-        KeyboardState { key: [false; 16] }
+        KeyboardState {
+            key: [false; 16],
+            waiting: false,
+            released: None,
+            pressed_at: [0; 16],
+            presses: 0,
+        }
     }
 
-    pub fn handle_input(&mut self, key_code: VirtualKeyCode, pressed: bool) {
-        match key_code {
-            VirtualKeyCode::Key1 => self.key[0x1] = pressed,
-            VirtualKeyCode::Key2 => self.key[0x2] = pressed,
-            VirtualKeyCode::Key3 => self.key[0x3] = pressed,
-            VirtualKeyCode::Key4 => self.key[0xC] = pressed,
-
-            VirtualKeyCode::Q => self.key[0x4] = pressed,
-            VirtualKeyCode::W => self.key[0x5] = pressed,
-            VirtualKeyCode::E => self.key[0x6] = pressed,
-            VirtualKeyCode::R => self.key[0xD] = pressed,
-
-            VirtualKeyCode::A => self.key[0x7] = pressed,
-            VirtualKeyCode::S => self.key[0x8] = pressed,
-            VirtualKeyCode::D => self.key[0x9] = pressed,
-            VirtualKeyCode::F => self.key[0xE] = pressed,
-
-            VirtualKeyCode::Z => self.key[0xA] = pressed,
-            VirtualKeyCode::X => self.key[0x0] = pressed,
-            VirtualKeyCode::C => self.key[0xB] = pressed,
-            VirtualKeyCode::V => self.key[0xF] = pressed,
-            _ => (),
+    pub fn set_key(&mut self, key: Key, pressed: bool) {
+        let index = key.hex() as usize;
+        if pressed && !self.key[index] {
+            self.presses += 1;
+            self.pressed_at[index] = self.presses;
+        } else if !pressed && self.key[index] && self.waiting {
+            let pressed_later =
+                |other: Key| self.pressed_at[index] > self.pressed_at[other.hex() as usize];
+            if self.released.is_none_or(pressed_later) {
+                self.released = Some(key);
+            }
         }
+        self.key[index] = pressed;
     }
 
-    pub fn any_pressed(&self) -> Option<usize> {
-        for (i, &state) in self.key.iter().enumerate() {
-            if state {
-                return Some(i);
-            }
+    /// Releases all keys and stops waiting for a key release.
+    pub fn release_all(&mut self) {
+        self.key = [false; 16];
+        self.waiting = false;
+        self.released = None;
+    }
+
+    /// Waits for a key that is down to be released, as done by the COSMAC VIP.
+    ///
+    /// The first call starts waiting and returns `None`. Subsequent calls return `None` until a
+    /// key has been released since waiting started, and then return that key.
+    ///
+    /// If several keys were released since the last call, the one that was pressed most recently
+    /// is returned, regardless of the order they were released in.
+    pub fn wait_for_release(&mut self) -> Option<Key> {
+        if !self.waiting {
+            self.waiting = true;
+            self.released = None;
+            return None;
+        }
+
+        let released = self.released.take();
+        if released.is_some() {
+            self.waiting = false;
         }
-        None
+        released
     }
 }
@@ -0,0 +1,147 @@
+use crate::keyboard::Key;
+#[cfg(feature = "gamepad")]
+use gilrs::Button;
+use std::collections::HashMap;
+use winit::event::VirtualKeyCode;
+
+/// A mapping from keys on the host keyboard, and buttons on gamepads with the `gamepad`
+/// feature, to the CHIP-8 keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyMap {
+    map: HashMap<VirtualKeyCode, Key>,
+    #[cfg(feature = "gamepad")]
+    buttons: HashMap<Button, Key>,
+}
+
+/// The names accepted by [`KeyMap::parse_button`], and the buttons they refer to.
+#[cfg(feature = "gamepad")]
+const BUTTON_NAMES: [(&str, Button); 14] = [
+    ("up", Button::DPadUp),
+    ("down", Button::DPadDown),
+    ("left", Button::DPadLeft),
+    ("right", Button::DPadRight),
+    ("south", Button::South),
+    ("east", Button::East),
+    ("west", Button::West),
+    ("north", Button::North),
+    ("lb", Button::LeftTrigger),
+    ("rb", Button::RightTrigger),
+    ("lt", Button::LeftTrigger2),
+    ("rt", Button::RightTrigger2),
+    ("select", Button::Select),
+    ("start", Button::Start),
+];
+
+impl KeyMap {
+    /// The names accepted by [`KeyMap::preset`].
+    pub const PRESET_NAMES: [&'static str; 2] = ["qwerty", "arcade"];
+
+    /// Returns the key map preset called `name`, if there is one.
+    pub fn preset(name: &str) -> Option<KeyMap> {
+        match name {
+            "qwerty" => Some(KeyMap::default()),
+            "arcade" => Some(KeyMap::arcade()),
+            _ => None,
+        }
+    }
+
+    /// The QWERTY layout with the arrow keys, Z and X added for games that only poll a few keys.
+    ///
+    /// - Up and Down are 1 and 4, the left paddle in Pong.
+    /// - Left and Right are 4 and 6, used for moving in Brix, Space Invaders and Tank.
+    /// - Z is 5, the fire button in Space Invaders, and X is 6.
+    ///
+    /// Z and X no longer reach A and 0 in this layout.
+    pub fn arcade() -> Self {
+        let mut keymap = KeyMap::default();
+        keymap.map.extend([
+            (VirtualKeyCode::Up, Key::Num1),
+            (VirtualKeyCode::Down, Key::Num4),
+            (VirtualKeyCode::Left, Key::Num4),
+            (VirtualKeyCode::Right, Key::Num6),
+            (VirtualKeyCode::Z, Key::Num5),
+            (VirtualKeyCode::X, Key::Num6),
+        ]);
+        keymap
+    }
+
+    /// Creates a mapping from `(key code, CHIP-8 key)` pairs.
+    ///
+    /// With the `gamepad` feature, gamepad buttons get the default mapping described in
+    /// [`KeyMap::insert_button`].
+    pub fn from_pairs(pairs: &[(VirtualKeyCode, Key)]) -> Self {
+        KeyMap {
+            map: pairs.iter().copied().collect(),
+            #[cfg(feature = "gamepad")]
+            buttons: HashMap::from([
+                (Button::DPadUp, Key::Num1),
+                (Button::DPadDown, Key::Num4),
+                (Button::DPadLeft, Key::Num4),
+                (Button::DPadRight, Key::Num6),
+                (Button::South, Key::Num5),
+                (Button::East, Key::Num6),
+                (Button::West, Key::Num4),
+                (Button::North, Key::Num1),
+                (Button::Start, Key::F),
+            ]),
+        }
+    }
+
+    /// Maps `key_code` to `key`, replacing any previous mapping of `key_code`.
+    pub fn insert(&mut self, key_code: VirtualKeyCode, key: Key) {
+        self.map.insert(key_code, key);
+    }
+
+    /// Returns the CHIP-8 key that `key_code` is mapped to.
+    pub fn get(&self, key_code: VirtualKeyCode) -> Option<Key> {
+        self.map.get(&key_code).copied()
+    }
+
+    /// Maps the gamepad `button` to `key`, replacing any previous mapping of `button`.
+    ///
+    /// By default the d-pad matches the arrow keys of [`KeyMap::arcade`], South (A on an Xbox
+    /// controller) is 5, East is 6, West is 4, North is 1 and Start is F.
+    #[cfg(feature = "gamepad")]
+    pub fn insert_button(&mut self, button: Button, key: Key) {
+        self.buttons.insert(button, key);
+    }
+
+    /// Returns the CHIP-8 key that the gamepad `button` is mapped to.
+    #[cfg(feature = "gamepad")]
+    pub fn get_button(&self, button: Button) -> Option<Key> {
+        self.buttons.get(&button).copied()
+    }
+
+    /// Returns the button called `name`, e.g. `up`, `south` or `start`.
+    #[cfg(feature = "gamepad")]
+    pub fn parse_button(name: &str) -> Option<Button> {
+        BUTTON_NAMES
+            .iter()
+            .find(|(button_name, _)| button_name.eq_ignore_ascii_case(name))
+            .map(|&(_, button)| button)
+    }
+}
+
+impl Default for KeyMap {
+    /// The QWERTY layout, mapping `1234`/`QWER`/`ASDF`/`ZXCV` to the CHIP-8 keypad.
+    fn default() -> Self {
+        KeyMap::from_pairs(&[
+            (VirtualKeyCode::Key1, Key::Num1),
+            (VirtualKeyCode::Key2, Key::Num2),
+            (VirtualKeyCode::Key3, Key::Num3),
+            (VirtualKeyCode::Key4, Key::C),
+            (VirtualKeyCode::Q, Key::Num4),
+            (VirtualKeyCode::W, Key::Num5),
+            (VirtualKeyCode::E, Key::Num6),
+            (VirtualKeyCode::R, Key::D),
+            (VirtualKeyCode::A, Key::Num7),
+            (VirtualKeyCode::S, Key::Num8),
+            (VirtualKeyCode::D, Key::Num9),
+            (VirtualKeyCode::F, Key::E),
+            (VirtualKeyCode::Z, Key::A),
+            (VirtualKeyCode::X, Key::Num0),
+            (VirtualKeyCode::C, Key::B),
+            (VirtualKeyCode::V, Key::F),
+        ])
+    }
+}
@@ -0,0 +1,58 @@
+use crate::{display::DisplayBuffer, interpreter::Interpreter, keyboard::Key, overlay::glyph};
+
+/// The keys in the order they're drawn, the rows of the COSMAC VIP keypad side by side.
+const LAYOUT: [[u8; 8]; 2] = [
+    [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF],
+];
+/// The width and height of a key including the gap after it, in CHIP-8 pixels.
+const KEY_SIZE: usize = DisplayBuffer::WIDTH / LAYOUT[0].len();
+/// How many CHIP-8 pixels the keypad adds below the display.
+pub const KEYPAD_HEIGHT: usize = KEY_SIZE * LAYOUT.len();
+
+const BACKGROUND_COLOR: [u8; 4] = [0x10, 0x10, 0x10, 0xFF];
+const KEY_COLOR: [u8; 4] = [0x40, 0x40, 0x40, 0xFF];
+const PRESSED_KEY_COLOR: [u8; 4] = [0xFF, 0xD0, 0x00, 0xFF];
+const LABEL_COLOR: [u8; 4] = [0xFF, 0xFF, 0xFF, 0xFF];
+const PRESSED_LABEL_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0xFF];
+
+/// Draws the keypad to the last rows of `frame`, below the display, highlighting the keys that are
+/// pressed.
+pub fn draw_keypad(interpreter: &Interpreter, frame: &mut [u8]) {
+    let start = frame.len() - DisplayBuffer::WIDTH * KEYPAD_HEIGHT * 4;
+    let keypad = &mut frame[start..];
+    for (i, pixel) in keypad.chunks_exact_mut(4).enumerate() {
+        let (x, y) = (i % DisplayBuffer::WIDTH, i / DisplayBuffer::WIDTH);
+        let (dx, dy) = (x % KEY_SIZE, y % KEY_SIZE);
+        // Leave a gap to the right of and below each key
+        if dx == KEY_SIZE - 1 || dy == KEY_SIZE - 1 {
+            pixel.copy_from_slice(&BACKGROUND_COLOR);
+            continue;
+        }
+
+        let key = LAYOUT[y / KEY_SIZE][x / KEY_SIZE];
+        let pressed = Key::from_hex(key).is_some_and(|key| interpreter.is_key_pressed(key));
+        // Center the 3x5 label in the 7x7 key
+        let label = glyph(
+            char::from_digit(key as u32, 16)
+                .unwrap()
+                .to_ascii_uppercase(),
+        );
+        let lit = (2..5).contains(&dx)
+            && (1..6).contains(&dy)
+            && label[dy - 1] & (0b100 >> (dx - 2)) != 0;
+        let color = match (pressed, lit) {
+            (false, false) => KEY_COLOR,
+            (false, true) => LABEL_COLOR,
+            (true, false) => PRESSED_KEY_COLOR,
+            (true, true) => PRESSED_LABEL_COLOR,
+        };
+        pixel.copy_from_slice(&color);
+    }
+}
+
+/// Returns the key at `(x, y)`, measured from the top left corner of the keypad, if any.
+pub fn key_at(x: usize, y: usize) -> Option<Key> {
+    let key = *LAYOUT.get(y / KEY_SIZE)?.get(x / KEY_SIZE)?;
+    Key::from_hex(key)
+}
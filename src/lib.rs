@@ -1,93 +1,68 @@
+#[cfg(feature = "zip")]
+mod archive;
+#[cfg(feature = "desktop")]
+mod args;
+mod asm;
+mod clock;
+#[cfg(feature = "desktop")]
+mod config;
+#[cfg(feature = "desktop")]
+mod debugger;
+#[cfg(feature = "desktop")]
+mod desktop;
+mod disasm;
 mod display;
+mod error;
+#[cfg(feature = "desktop")]
+mod filter;
+#[cfg(feature = "desktop")]
+mod headless;
 mod interpreter;
 mod keyboard;
+#[cfg(feature = "desktop")]
+mod keymap;
+#[cfg(feature = "desktop")]
+mod keypad;
 mod memory;
+#[cfg(feature = "net")]
+mod net;
+#[cfg(feature = "desktop")]
+mod overlay;
+mod palette;
+mod quirks;
+mod rom;
+#[cfg(feature = "desktop")]
+mod screenshot;
+mod stack;
 mod timer;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-use interpreter::Interpreter;
-use pixels::{Pixels, SurfaceTexture};
-use std::fs::File;
-use std::io::Read;
-use winit::event_loop::{ControlFlow, EventLoop};
-use winit::{
-    dpi::LogicalSize,
-    event::{self, Event},
-    window::WindowBuilder,
+#[cfg(feature = "zip")]
+pub use archive::load_rom_from_zip;
+pub use asm::{assemble, AsmError};
+pub use clock::{Clock, SystemClock};
+#[cfg(feature = "desktop")]
+pub use config::Config;
+#[cfg(feature = "desktop")]
+pub use desktop::{load_rom_bytes, run, run_rom};
+pub use disasm::disassemble;
+pub use display::{DisplayBuffer, DrawMode, FadeBuffer};
+pub use error::Chip8Error;
+pub use interpreter::{
+    decode, ControlFlow, DrawEvent, ExecuteError, FrameInfo, Instruction, Interpreter,
+    InterpreterState, InvalidState, SelfModifyingWrite, Timing, TraceEntry, UnknownOpcodePolicy,
 };
-
-const WINDOW_WIDTH: u32 = 512;
-const WINDOW_HEIGHT: u32 = 256;
-
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args();
-    args.next();
-    let game_path_relative = args.next().ok_or("Must enter path to a game")?;
-    let game_path = std::env::current_dir()?.join(game_path_relative);
-    println!("Loading game from {:?}...", game_path);
-    let mut game_data = Vec::new();
-    let mut game_file = File::open(game_path)?;
-    game_file.read_to_end(&mut game_data)?;
-
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new()
-        .with_title("CHIP 8")
-        .with_inner_size(LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT))
-        .build(&event_loop)?;
-
-    let mut pixels = {
-        let surface_texture = SurfaceTexture::new(WINDOW_WIDTH, WINDOW_HEIGHT, &window);
-        Pixels::new(64, 32, surface_texture)?
-    };
-
-    let mut interpreter = Interpreter::new(&game_data);
-
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Poll;
-
-        match event {
-            Event::MainEventsCleared => window.request_redraw(),
-            Event::RedrawRequested(_) => {
-                interpreter.run_cycle();
-
-                let display_buffer = interpreter.get_display_buffer();
-                render(display_buffer, pixels.get_frame());
-                pixels.render().unwrap();
-            }
-            Event::WindowEvent {
-                event:
-                    event::WindowEvent::KeyboardInput {
-                        input:
-                            event::KeyboardInput {
-                                virtual_keycode: Some(key_code),
-                                state,
-                                ..
-                            },
-                        ..
-                    },
-                ..
-            } => {
-                if key_code == event::VirtualKeyCode::Escape {
-                    *control_flow = ControlFlow::Exit;
-                } else {
-                    interpreter.handle_input(key_code, state == event::ElementState::Pressed);
-                }
-            }
-            Event::WindowEvent {
-                event: event::WindowEvent::CloseRequested,
-                ..
-            } => *control_flow = ControlFlow::Exit,
-            _ => {}
-        }
-    });
-}
-
-fn render(display_buffer: &[bool], frame: &mut [u8]) {
-    for (pixel, dp) in frame.chunks_exact_mut(4).zip(display_buffer.iter()) {
-        let rgba = match dp {
-            true => [255, 255, 255, 255],
-            _ => [0, 0, 0, 255],
-        };
-
-        pixel.copy_from_slice(&rgba);
-    }
-}
+pub use keyboard::Key;
+#[cfg(feature = "desktop")]
+pub use keymap::KeyMap;
+pub use memory::{InvalidFontAddress, InvalidFontLength, LoadRomError, MemoryOutOfBounds};
+#[cfg(feature = "net")]
+pub use net::{Frame, SpectatorClient, SpectatorServer};
+pub use palette::{parse_color, Palette, ParseColorError};
+pub use quirks::Quirks;
+pub use rom::{byte_swap, looks_byte_swapped};
+#[cfg(feature = "desktop")]
+pub use screenshot::save_screenshot;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmInterpreter;
@@ -1,5 +1,22 @@
-const MEM_SIZE: usize = 4096;
-const SPRITES: [u8; 80] = [
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The memory size of CHIP-8 and SUPER-CHIP.
+pub const DEFAULT_MEMORY_SIZE: usize = 4096;
+/// The memory size of XO-CHIP, which can address 64 KiB with `F000 nnnn`.
+pub const XOCHIP_MEMORY_SIZE: usize = 65536;
+/// Where programs are loaded and start executing unless another entry point is given.
+pub const DEFAULT_ENTRY_POINT: usize = 0x200;
+/// Where the font is stored unless another address is set, by convention.
+pub const DEFAULT_FONT_ADDRESS: usize = 0x050;
+/// The number of bytes in a font, 5 bytes for each of the 16 hex digits.
+pub const FONT_SIZE: usize = 80;
+/// The classic font, used unless a custom one is set.
+pub const DEFAULT_FONT: [u8; FONT_SIZE] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
     0x20, 0x60, 0x20, 0x20, 0x70, // 1
     0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
@@ -18,22 +35,172 @@ const SPRITES: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+/// The error returned when reading past the last address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryOutOfBounds {
+    /// The first address that was out of bounds.
+    pub address: usize,
+}
+
+impl Display for MemoryOutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "memory access out of bounds: {:04X}", self.address)
+    }
+}
+
+impl Error for MemoryOutOfBounds {}
+
+/// The error returned when a ROM can't be loaded into memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadRomError {
+    /// The ROM has no instructions at all.
+    Empty,
+    /// The ROM doesn't fit between the entry point and the end of memory.
+    TooLarge { len: usize, max: usize },
+    /// The entry point is below 0x200, where the font lives, or past the end of memory.
+    InvalidEntryPoint(usize),
+}
+
+impl Display for LoadRomError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadRomError::Empty => write!(f, "the ROM is empty"),
+            LoadRomError::TooLarge { len, max } => write!(
+                f,
+                "the ROM is {} bytes, but at most {} bytes fit in memory",
+                len, max
+            ),
+            LoadRomError::InvalidEntryPoint(address) => write!(
+                f,
+                "the entry point {:04X} is outside the program area of memory",
+                address
+            ),
+        }
+    }
+}
+
+impl Error for LoadRomError {}
+
+/// The error returned when setting a font that isn't exactly 80 bytes long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidFontLength {
+    /// The length of the rejected font.
+    pub len: usize,
+}
+
+impl Display for InvalidFontLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "font must be {} bytes, got {}", FONT_SIZE, self.len)
+    }
+}
+
+impl Error for InvalidFontLength {}
+
+/// The error returned when moving the font to where it would overlap the program area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidFontAddress {
+    /// The rejected address.
+    pub address: usize,
+}
+
+impl Display for InvalidFontAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a font at {:04X} would overlap the program area at 0x200",
+            self.address
+        )
+    }
+}
+
+impl Error for InvalidFontAddress {}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Memory {
-    bytes: [u8; MEM_SIZE],
+    bytes: Vec<u8>,
+    font_address: usize,
 }
 
 impl Memory {
-    pub fn new() -> Self {
-        let mut mem = [0; MEM_SIZE];
-        mem[0..SPRITES.len()].copy_from_slice(&SPRITES);
+    /// Creates a memory of `size` bytes with the default font.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is smaller than 0x200, leaving no room for a program.
+    pub fn with_size(size: usize) -> Self {
+        assert!(size >= 0x200, "memory must be at least 0x200 bytes");
+        let mut mem = vec![0; size];
+        mem[DEFAULT_FONT_ADDRESS..DEFAULT_FONT_ADDRESS + FONT_SIZE].copy_from_slice(&DEFAULT_FONT);
+
+        Memory {
+            bytes: mem,
+            font_address: DEFAULT_FONT_ADDRESS,
+        }
+    }
+
+    /// Returns the address of the font.
+    pub fn font_address(&self) -> usize {
+        self.font_address
+    }
+
+    /// Moves the font to `address`, zeroing the bytes where it used to be.
+    ///
+    /// Returns an error if the font would overlap the program area starting at 0x200.
+    pub fn set_font_address(&mut self, address: usize) -> Result<(), InvalidFontAddress> {
+        if address + FONT_SIZE > 0x200 {
+            return Err(InvalidFontAddress { address });
+        }
+        let old = self.font_address..self.font_address + FONT_SIZE;
+        let mut font = [0; FONT_SIZE];
+        font.copy_from_slice(&self.bytes[old.clone()]);
+        self.bytes[old].fill(0);
+        self.bytes[address..address + FONT_SIZE].copy_from_slice(&font);
+        self.font_address = address;
+        Ok(())
+    }
+
+    /// Replaces the font with `font`, 5 bytes for each hex digit from 0 through F, leaving the
+    /// rest of memory untouched.
+    pub fn set_font(&mut self, font: &[u8]) -> Result<(), InvalidFontLength> {
+        if font.len() != FONT_SIZE {
+            return Err(InvalidFontLength { len: font.len() });
+        }
+        self.bytes[self.font_address..self.font_address + FONT_SIZE].copy_from_slice(font);
+        Ok(())
+    }
 
-        Memory { bytes: mem }
+    /// Returns the number of addressable bytes.
+    pub fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Grows or shrinks the memory to `size` bytes, keeping the bytes below `size` and zeroing
+    /// any new ones.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is smaller than 0x200, leaving no room for a program.
+    pub fn resize(&mut self, size: usize) {
+        assert!(size >= 0x200, "memory must be at least 0x200 bytes");
+        self.bytes.resize(size, 0);
     }
 
     pub fn read_byte(&self, address: usize) -> u8 {
         self.bytes[address]
     }
 
+    /// Returns the `len` bytes starting at `start`.
+    pub fn dump(&self, start: usize, len: usize) -> Result<&[u8], MemoryOutOfBounds> {
+        let end = start.saturating_add(len);
+        if end > self.bytes.len() {
+            return Err(MemoryOutOfBounds {
+                address: start.max(self.bytes.len()),
+            });
+        }
+        Ok(&self.bytes[start..end])
+    }
+
     pub fn write_byte(&mut self, address: usize, value: u8) {
         self.bytes[address] = value;
     }
@@ -44,25 +211,11 @@ impl Memory {
     ///
     /// Panics if the given sprite is outside of 0x0 through 0xF
     pub fn sprite_address(&self, hex_sprite: u8) -> usize {
-        match hex_sprite {
-            0x0 => 0,
-            0x1 => 5,
-            0x2 => 10,
-            0x3 => 15,
-            0x4 => 20,
-            0x5 => 25,
-            0x6 => 30,
-            0x7 => 35,
-            0x8 => 40,
-            0x9 => 45,
-            0xA => 50,
-            0xB => 55,
-            0xC => 60,
-            0xD => 65,
-            0xE => 70,
-            0xF => 75,
-            _ => panic!("invalid sprite: tried to get address of invalid sprite"),
-        }
+        assert!(
+            hex_sprite <= 0xF,
+            "invalid sprite: tried to get address of invalid sprite"
+        );
+        self.font_address + hex_sprite as usize * (FONT_SIZE / 16)
     }
 
     /// Reads a sprite of `length` bytes, starting at `address`.
@@ -74,7 +227,32 @@ impl Memory {
         &self.bytes[address..address + length]
     }
 
-    pub fn load_rom(&mut self, rom: &[u8]) {
-        self.bytes[0x200..0x200 + rom.len()].copy_from_slice(rom);
+    /// Copies `rom` into memory starting at `entry_point`.
+    pub fn load_rom(&mut self, rom: &[u8], entry_point: usize) -> Result<(), LoadRomError> {
+        self.check_rom(rom, entry_point)?;
+        debug_assert!(
+            self.font_address + FONT_SIZE <= entry_point,
+            "loading the ROM would overwrite the font"
+        );
+        self.bytes[entry_point..entry_point + rom.len()].copy_from_slice(rom);
+        Ok(())
+    }
+
+    /// Returns an error if `rom` is empty or doesn't fit in memory when loaded at `entry_point`,
+    /// or if `entry_point` is below 0x200 or past the end of memory.
+    pub fn check_rom(&self, rom: &[u8], entry_point: usize) -> Result<(), LoadRomError> {
+        if !(DEFAULT_ENTRY_POINT..self.bytes.len()).contains(&entry_point) {
+            return Err(LoadRomError::InvalidEntryPoint(entry_point));
+        }
+        let max = self.bytes.len() - entry_point;
+        if rom.is_empty() {
+            return Err(LoadRomError::Empty);
+        } else if rom.len() > max {
+            return Err(LoadRomError::TooLarge {
+                len: rom.len(),
+                max,
+            });
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,116 @@
+use crate::DisplayBuffer;
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// A packed display frame.
+pub type Frame = [u8; DisplayBuffer::PACKED_SIZE];
+
+/// Streams the display to spectators over TCP.
+///
+/// Each message is a frame from [`DisplayBuffer::as_packed`], prefixed with its length as a
+/// big-endian `u32`.
+pub struct SpectatorServer {
+    listener: TcpListener,
+    clients: Vec<TcpStream>,
+}
+
+impl SpectatorServer {
+    /// Starts listening for spectators on `address`.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(SpectatorServer {
+            listener,
+            clients: Vec::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns the number of connected spectators, as of the last broadcast.
+    pub fn spectators(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Accepts new spectators and sends `frame` to all of them.
+    ///
+    /// Never blocks; spectators that disconnect or can't keep up are dropped.
+    pub fn broadcast(&mut self, frame: &Frame) {
+        while let Ok((stream, address)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                let _ = stream.set_nodelay(true);
+                println!("Spectator connected from {}", address);
+                self.clients.push(stream);
+            }
+        }
+
+        let mut message = Vec::with_capacity(4 + frame.len());
+        write_frame(&mut message, frame).unwrap();
+        self.clients
+            .retain_mut(|client| client.write_all(&message).is_ok());
+    }
+}
+
+/// Receives frames from a [`SpectatorServer`] on a background thread.
+pub struct SpectatorClient {
+    latest: Arc<Mutex<Option<Frame>>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl SpectatorClient {
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+        let latest = Arc::new(Mutex::new(None));
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let thread_latest = latest.clone();
+        let thread_connected = connected.clone();
+        thread::spawn(move || {
+            while let Ok(frame) = read_frame(&mut stream) {
+                *thread_latest.lock().unwrap() = Some(frame);
+            }
+            thread_connected.store(false, Ordering::Release);
+        });
+
+        Ok(SpectatorClient { latest, connected })
+    }
+
+    /// Returns the newest frame received since the last call, skipping any older ones.
+    pub fn take_frame(&self) -> Option<Frame> {
+        self.latest.lock().unwrap().take()
+    }
+
+    /// Returns whether the connection to the server is still open.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Acquire)
+    }
+}
+
+fn write_frame<W: Write>(writer: &mut W, frame: &Frame) -> io::Result<()> {
+    writer.write_all(&(frame.len() as u32).to_be_bytes())?;
+    writer.write_all(frame)
+}
+
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<Frame> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    if u32::from_be_bytes(length) as usize != DisplayBuffer::PACKED_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unexpected frame size",
+        ));
+    }
+
+    let mut frame = [0; DisplayBuffer::PACKED_SIZE];
+    reader.read_exact(&mut frame)?;
+    Ok(frame)
+}
@@ -0,0 +1,94 @@
+use crate::{display::DisplayBuffer, interpreter::Interpreter};
+
+/// The width of a glyph including the space after it.
+const ADVANCE: usize = 4;
+/// The height of a line including the space below it.
+const LINE_HEIGHT: usize = 6;
+const TEXT_COLOR: [u8; 4] = [0xFF, 0xD0, 0x00, 0xFF];
+
+/// Draws the registers, timers and top of the stack of `interpreter` over `frame`, dimming the
+/// display behind the text.
+///
+/// The lines show PC and I, V0-V7 and V8-VF as pairs of hex digits, the delay and sound timers,
+/// and the stack depth followed by the most recent return address.
+pub fn draw_overlay(interpreter: &Interpreter, frame: &mut [u8]) {
+    for pixel in frame.chunks_exact_mut(4) {
+        for channel in &mut pixel[..3] {
+            *channel /= 4;
+        }
+    }
+
+    let registers = interpreter.registers();
+    let hex = |values: &[u8]| {
+        values
+            .iter()
+            .map(|v| format!("{:02X}", v))
+            .collect::<String>()
+    };
+    let stack = interpreter.stack_frames();
+    let top = match stack.last() {
+        Some(address) => format!("{:04X}", address),
+        None => "----".to_string(),
+    };
+    let lines = [
+        format!("PC{:03X} I{:03X}", interpreter.pc(), interpreter.reg_i()),
+        hex(&registers[..8]),
+        hex(&registers[8..]),
+        format!(
+            "DT{:02X} ST{:02X}",
+            interpreter.delay_timer(),
+            interpreter.sound_timer()
+        ),
+        format!("SP{:X} {}", interpreter.stack_depth(), top),
+    ];
+
+    for (row, line) in lines.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            draw_glyph(frame, glyph(c), column * ADVANCE + 1, row * LINE_HEIGHT + 1);
+        }
+    }
+}
+
+fn draw_glyph(frame: &mut [u8], glyph: [u8; 5], x: usize, y: usize) {
+    for (dy, row) in glyph.iter().enumerate() {
+        for dx in 0..3 {
+            let (px, py) = (x + dx, y + dy);
+            if row & (0b100 >> dx) == 0 || px >= DisplayBuffer::WIDTH || py >= DisplayBuffer::HEIGHT
+            {
+                continue;
+            }
+            let i = (py * DisplayBuffer::WIDTH + px) * 4;
+            frame[i..i + 4].copy_from_slice(&TEXT_COLOR);
+        }
+    }
+}
+
+/// Returns the 3x5 bitmap of `c`, one row per byte with the leftmost pixel in bit 2.
+///
+/// Only hex digits and the letters used in the labels are included, anything else is blank.
+pub(crate) fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b011, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => [0; 5],
+    }
+}
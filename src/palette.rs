@@ -0,0 +1,72 @@
+use std::{error::Error, fmt};
+
+/// The colors used to draw lit (`fg`) and unlit (`bg`) pixels, as RGBA.
+///
+/// XO-CHIP programs can also light pixels in the second plane, drawn with `fg2`, or in both
+/// planes, drawn with `fg_both`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub fg: [u8; 4],
+    pub bg: [u8; 4],
+    pub fg2: [u8; 4],
+    pub fg_both: [u8; 4],
+}
+
+impl Default for Palette {
+    /// White on black, with orange and brown for the second plane and both planes.
+    fn default() -> Self {
+        Palette {
+            fg: [255, 255, 255, 255],
+            bg: [0, 0, 0, 255],
+            fg2: [255, 102, 0, 255],
+            fg_both: [102, 34, 0, 255],
+        }
+    }
+}
+
+impl Palette {
+    /// Returns the color of a pixel value from [`DisplayBuffer::pixel_values`].
+    ///
+    /// [`DisplayBuffer::pixel_values`]: crate::DisplayBuffer::pixel_values
+    pub fn color(&self, value: u8) -> [u8; 4] {
+        match value {
+            0 => self.bg,
+            1 => self.fg,
+            2 => self.fg2,
+            _ => self.fg_both,
+        }
+    }
+
+    /// Returns the color between `bg` (intensity 0) and `fg` (intensity 255).
+    pub fn blend(&self, intensity: u8) -> [u8; 4] {
+        let mut rgba = [0; 4];
+        for ((c, &fg), &bg) in rgba.iter_mut().zip(&self.fg).zip(&self.bg) {
+            let (fg, bg, intensity) = (fg as u32, bg as u32, intensity as u32);
+            *c = ((fg * intensity + bg * (255 - intensity)) / 255) as u8;
+        }
+        rgba
+    }
+}
+
+/// An error from parsing a color that isn't of the form `RRGGBB`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid color {:?}: expected RRGGBB", self.0)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parses a hexadecimal `RRGGBB` color, optionally prefixed with `#`, into an opaque RGBA color.
+pub fn parse_color(s: &str) -> Result<[u8; 4], ParseColorError> {
+    let digits = s.strip_prefix('#').unwrap_or(s);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ParseColorError(s.to_string()));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+    Ok([channel(0), channel(2), channel(4), 255])
+}
@@ -0,0 +1,191 @@
+/// Toggles for behavior that differs between CHIP-8 interpreters.
+///
+/// The defaults keep the behavior chippy8 has always had, which most ROMs expect. Use
+/// [`Quirks::preset`] to get the combination used by a specific platform, e.g. `chip8` for the
+/// original COSMAC VIP interpreter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    /// `8xy6` and `8xyE` shift `Vx` in place instead of shifting `Vy` into `Vx`.
+    pub shift_uses_vx: bool,
+    /// `Fx55` and `Fx65` leave `I` pointing past the last register stored or loaded, i.e. they
+    /// increment it by `x + 1`.
+    pub load_store_increments_i: bool,
+    /// `Bnnn` jumps to `nnn + Vx`, where `x` is the highest nibble of `nnn`, instead of
+    /// `nnn + V0`.
+    pub jump_uses_vx: bool,
+    /// `Fx1E` sets `VF` to 1 if `I` exceeds 0x0FFF after the addition, and to 0 otherwise.
+    ///
+    /// When disabled, `VF` is left untouched.
+    pub i_overflow_sets_vf: bool,
+    /// `Dxyn` sets `VF` to the number of sprite rows that collided with lit pixels, instead of
+    /// to 1 for any collision.
+    pub collision_counts_rows: bool,
+    /// `Dxyn` drops the parts of a sprite that go past the edge of the display, instead of
+    /// wrapping them around to the opposite edge.
+    pub clip_sprites: bool,
+    /// Decode the XO-CHIP instructions `F000 nnnn`, `5xy2`, `5xy3` and `Fn01`, and let skip
+    /// instructions skip over all four bytes of `F000 nnnn`.
+    pub xochip_opcodes: bool,
+    /// `Dxyn` waits for the next 60 Hz frame before execution continues, like the COSMAC VIP
+    /// waiting for the vertical blank. Limits games to one sprite per frame.
+    pub display_wait: bool,
+    /// `8xy1`, `8xy2` and `8xy3` set `VF` to 0 after the operation, a side effect of how the
+    /// COSMAC VIP implemented them.
+    ///
+    /// When disabled, `VF` is left untouched.
+    pub logic_resets_vf: bool,
+    /// Support the 64x64 hires mode of some COSMAC VIP programs. These start with `1260`, a jump
+    /// into a patched copy of the interpreter, which is emulated by switching the display to
+    /// 64x64 and continuing at 0x2C0 instead. `0230` then clears the display.
+    ///
+    /// Off in every preset, since it changes what `1260` at 0x200 does.
+    pub vip_hires: bool,
+}
+
+impl Quirks {
+    /// The names accepted by [`Quirks::preset`].
+    pub const PRESET_NAMES: [&'static str; 3] = ["chip8", "schip", "xochip"];
+
+    /// Returns the quirks of a platform by name:
+    ///
+    /// - `chip8`: the original COSMAC VIP interpreter, which unlike [`Quirks::default`] also
+    ///   increments `I` in `Fx55` and `Fx65`, resets `VF` after logic instructions and waits for
+    ///   the display in `Dxyn`
+    /// - `schip`: SUPER-CHIP 1.1 on the HP 48
+    /// - `xochip`: XO-CHIP, as implemented by Octo
+    pub fn preset(name: &str) -> Option<Quirks> {
+        let quirks = match name {
+            "chip8" => Quirks {
+                shift_uses_vx: false,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                i_overflow_sets_vf: false,
+                collision_counts_rows: false,
+                clip_sprites: true,
+                xochip_opcodes: false,
+                display_wait: true,
+                logic_resets_vf: true,
+                vip_hires: false,
+            },
+            "schip" => Quirks {
+                shift_uses_vx: true,
+                load_store_increments_i: false,
+                jump_uses_vx: true,
+                i_overflow_sets_vf: false,
+                collision_counts_rows: true,
+                clip_sprites: true,
+                xochip_opcodes: false,
+                display_wait: false,
+                logic_resets_vf: false,
+                vip_hires: false,
+            },
+            "xochip" => Quirks {
+                shift_uses_vx: false,
+                load_store_increments_i: true,
+                jump_uses_vx: false,
+                i_overflow_sets_vf: false,
+                collision_counts_rows: false,
+                clip_sprites: false,
+                xochip_opcodes: true,
+                display_wait: false,
+                logic_resets_vf: false,
+                vip_hires: false,
+            },
+            _ => return None,
+        };
+
+        Some(quirks)
+    }
+}
+
+impl Quirks {
+    /// Guesses the quirks a ROM needs.
+    ///
+    /// ROMs using XO-CHIP or SUPER-CHIP only instructions get the `xochip` or `schip` preset.
+    /// Only the instructions reachable from 0x200 are checked, so sprites and other data can't be
+    /// mistaken for them. Returns `None` if nothing indicates that the ROM is made for anything
+    /// other than the original CHIP-8.
+    pub fn detect(rom: &[u8]) -> Option<Quirks> {
+        let mut schip = false;
+        for [upper, lower] in reachable_opcodes(rom) {
+            let nibbles = [upper >> 4, upper & 0xF, lower >> 4, lower & 0xF];
+            match nibbles {
+                // Long load, save/load range, audio, plane select, pitch and scroll up
+                [0xF, 0x0, 0x0, 0x0]
+                | [0x5, _, _, 0x2 | 0x3]
+                | [0xF, 0x0, 0x0, 0x2]
+                | [0xF, _, 0x0, 0x1]
+                | [0xF, _, 0x3, 0xA]
+                | [0x0, 0x0, 0xD, _] => return Quirks::preset("xochip"),
+                // Scrolling, exit, resolution, large font and RPL flags
+                [0x0, 0x0, 0xC, _]
+                | [0x0, 0x0, 0xF, 0xB..=0xF]
+                | [0xF, _, 0x3, 0x0]
+                | [0xF, _, 0x7 | 0x8, 0x5] => schip = true,
+                _ => (),
+            }
+        }
+
+        if schip {
+            Quirks::preset("schip")
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the opcodes that can be reached by following the control flow of `rom` from its first
+/// instruction, loaded at 0x200.
+///
+/// Jumps through `Bnnn` can't be followed, so code only reachable that way is missed.
+fn reachable_opcodes(rom: &[u8]) -> Vec<[u8; 2]> {
+    let mut visited = vec![false; rom.len()];
+    let mut opcodes = Vec::new();
+    let mut pending = vec![0];
+    while let Some(offset) = pending.pop() {
+        if offset + 1 >= rom.len() || visited[offset] {
+            continue;
+        }
+        visited[offset] = true;
+        let [upper, lower] = [rom[offset], rom[offset + 1]];
+        opcodes.push([upper, lower]);
+
+        let target = (((upper as usize & 0xF) << 8) | lower as usize).checked_sub(0x200);
+        let long = |offset: usize| rom.get(offset..offset + 2) == Some(&[0xF0, 0x00][..]);
+        match [upper >> 4, upper & 0xF, lower >> 4, lower & 0xF] {
+            // Return, exit and jumps through a register end the path
+            [0x0, 0x0, 0xE, 0xE] | [0x0, 0x0, 0xF, 0xD] | [0xB, ..] => (),
+            [0x1, ..] => pending.extend(target),
+            [0x2, ..] => {
+                pending.extend(target);
+                pending.push(offset + 2);
+            }
+            // The long load's address isn't an instruction
+            [0xF, 0x0, 0x0, 0x0] => pending.push(offset + 4),
+            [0x3 | 0x4, ..] | [0x5 | 0x9, _, _, 0x0] | [0xE, _, 0x9, 0xE] | [0xE, _, 0xA, 0x1] => {
+                let next = offset + 2;
+                let skip = if long(next) { 4 } else { 2 };
+                pending.extend([next, next + skip]);
+            }
+            _ => pending.push(offset + 2),
+        }
+    }
+    opcodes
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks {
+            shift_uses_vx: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            i_overflow_sets_vf: false,
+            collision_counts_rows: false,
+            clip_sprites: true,
+            xochip_opcodes: false,
+            display_wait: false,
+            logic_resets_vf: false,
+            vip_hires: false,
+        }
+    }
+}
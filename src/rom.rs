@@ -0,0 +1,38 @@
+use crate::interpreter::{decode, Instruction};
+
+/// The share of a ROM's opcodes that have to be suspicious before the ROM is suspected of being
+/// byte-swapped, see [`looks_byte_swapped`].
+const SUSPICIOUS_SHARE: f64 = 0.2;
+
+/// Guesses whether `rom` was dumped with the bytes of each instruction swapped, which makes it
+/// run as garbage.
+///
+/// A ROM is suspected if many of its opcodes are unknown or call machine code routines, which
+/// real programs rarely do, and swapping the bytes makes at most half as many suspicious.
+pub fn looks_byte_swapped(rom: &[u8]) -> bool {
+    let opcodes = rom.len() / 2;
+    let suspicious = count_suspicious(rom, u16::from_be_bytes);
+    let swapped = count_suspicious(rom, u16::from_le_bytes);
+    opcodes > 0
+        && suspicious as f64 >= opcodes as f64 * SUSPICIOUS_SHARE
+        && swapped * 2 <= suspicious
+}
+
+/// Returns `rom` with the two bytes of each instruction swapped. A trailing odd byte is kept as
+/// is.
+pub fn byte_swap(rom: &[u8]) -> Vec<u8> {
+    let mut swapped = rom.to_vec();
+    for pair in swapped.chunks_exact_mut(2) {
+        pair.swap(0, 1);
+    }
+    swapped
+}
+
+/// Counts the opcodes in `rom` that are unknown or call machine code routines, reading each
+/// pair of bytes with `opcode`.
+fn count_suspicious(rom: &[u8], opcode: fn([u8; 2]) -> u16) -> usize {
+    rom.chunks_exact(2)
+        .map(|pair| decode(opcode([pair[0], pair[1]])))
+        .filter(|instruction| matches!(instruction, None | Some(Instruction::MachineRoutine(_))))
+        .count()
+}
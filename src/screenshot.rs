@@ -0,0 +1,25 @@
+use crate::{display::DisplayBuffer, palette::Palette};
+use image::{ImageResult, Rgba, RgbaImage};
+use std::path::Path;
+
+/// Saves `display` as a PNG image at `path`, drawing each CHIP-8 pixel as a `scale` x `scale`
+/// square.
+///
+/// All rows are included in hires mode, and pixels get the palette color of their value in both
+/// XO-CHIP planes, like in the window.
+pub fn save_screenshot(
+    display: &DisplayBuffer,
+    palette: &Palette,
+    scale: u32,
+    path: &Path,
+) -> ImageResult<()> {
+    let values = display.all_pixel_values();
+    let width = DisplayBuffer::WIDTH as u32;
+    let height = display.height() as u32;
+    let image = RgbaImage::from_fn(width * scale, height * scale, |x, y| {
+        let index = (y / scale * width + x / scale) as usize;
+        Rgba(palette.color(values[index]))
+    });
+
+    image.save(path)
+}
@@ -0,0 +1,49 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The number of return addresses the stack can hold, as on the original hardware.
+pub const STACK_SIZE: usize = 16;
+
+/// The error returned when pushing to a full stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackOverflow;
+
+/// The error returned when popping from an empty stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StackUnderflow;
+
+/// The call stack, holding return addresses for subroutines.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Stack {
+    frames: Vec<u16>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Stack {
+            frames: Vec::with_capacity(STACK_SIZE),
+        }
+    }
+
+    pub fn push(&mut self, address: u16) -> Result<(), StackOverflow> {
+        if self.frames.len() == STACK_SIZE {
+            return Err(StackOverflow);
+        }
+        self.frames.push(address);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<u16, StackUnderflow> {
+        self.frames.pop().ok_or(StackUnderflow)
+    }
+
+    /// Returns the return addresses, starting with the oldest.
+    pub fn frames(&self) -> &[u16] {
+        &self.frames
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
@@ -1,30 +1,65 @@
-use std::time::{Duration, Instant};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 const PERIOD: Duration = Duration::from_micros(16666);
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Timers {
     pub delay_timer: u8,
     pub sound_timer: u8,
-    last_tick: Instant,
+    /// The clock reading at the last tick.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    last_tick: Duration,
+    /// The number of times the timers have counted down, i.e. the number of 60 Hz frames.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    ticks: u64,
 }
 
 impl Timers {
-    pub fn new() -> Self {
+    pub fn new(now: Duration) -> Self {
         Timers {
             delay_timer: 0,
             sound_timer: 0,
-            last_tick: Instant::now(),
+            last_tick: now,
+            ticks: 0,
         }
     }
 
-    pub fn tick(&mut self) -> Duration {
-        let now = Instant::now();
-        let diff = now - self.last_tick;
-        if diff < PERIOD {
+    /// Returns the number of times the timers have counted down, whether or not they were
+    /// zero.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Counts the timers down once for every period that has passed since the last tick at
+    /// `now`, and returns the time since the last tick if no period has passed.
+    ///
+    /// `speed` scales the rate, e.g. 2.0 counts down at 120 Hz instead of 60 Hz.
+    pub fn tick(&mut self, now: Duration, speed: f32) -> Duration {
+        let period = PERIOD.div_f32(speed);
+        let diff = now.saturating_sub(self.last_tick);
+        if diff < period {
             return diff;
         }
         self.last_tick = now;
 
+        let ticks = (diff.as_secs_f64() / period.as_secs_f64()) as u64;
+        for _ in 0..ticks.min(u8::MAX as u64) {
+            self.decrement();
+        }
+
+        Duration::ZERO
+    }
+
+    /// Starts counting the current period from `now`, e.g. after switching clocks.
+    pub fn restart(&mut self, now: Duration) {
+        self.last_tick = now;
+    }
+
+    pub fn decrement(&mut self) {
+        self.ticks += 1;
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -32,7 +67,5 @@ impl Timers {
         if self.sound_timer > 0 {
             self.sound_timer -= 1;
         }
-
-        Duration::ZERO
     }
 }
@@ -0,0 +1,73 @@
+use crate::{DisplayBuffer, Interpreter, Key};
+use wasm_bindgen::prelude::*;
+
+/// JavaScript bindings for [`Interpreter`].
+///
+/// The page owns the clock: it calls `step` as often as the game should run, `tick_timers` 60
+/// times per second, and draws `framebuffer` to a canvas.
+#[wasm_bindgen]
+pub struct WasmInterpreter {
+    interpreter: Interpreter,
+}
+
+#[wasm_bindgen]
+impl WasmInterpreter {
+    /// Creates an interpreter with `rom` loaded, taking the ROM as a `Uint8Array`. Throws if the
+    /// ROM is empty or too large.
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom: &[u8]) -> Result<WasmInterpreter, JsError> {
+        let interpreter = Interpreter::try_new(rom).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(WasmInterpreter { interpreter })
+    }
+
+    /// Executes one instruction, throwing the error message if it fails.
+    pub fn step(&mut self) -> Result<(), JsError> {
+        self.interpreter
+            .step()
+            .map_err(|e| JsError::new(&e.to_string()))
+    }
+
+    pub fn tick_timers(&mut self) {
+        self.interpreter.tick_timers();
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.interpreter.delay_timer()
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.interpreter.sound_timer()
+    }
+
+    /// Returns whether the ROM is stuck in a jump to itself, so stepping can stop.
+    pub fn is_halted(&self) -> bool {
+        self.interpreter.is_halted()
+    }
+
+    pub fn width() -> usize {
+        DisplayBuffer::WIDTH
+    }
+
+    pub fn height() -> usize {
+        DisplayBuffer::HEIGHT
+    }
+
+    /// Returns the display as a `Uint8Array` with one byte per pixel, row by row. Bit 0 is set
+    /// if the pixel is lit in the first plane and bit 1 if it's lit in the second plane, so
+    /// plain CHIP-8 programs only produce 0 and 1.
+    pub fn framebuffer(&self) -> Vec<u8> {
+        self.interpreter.get_display_values().to_vec()
+    }
+
+    /// Returns whether the display has changed since the last call.
+    pub fn take_display_changed(&mut self) -> bool {
+        self.interpreter.take_display_changed()
+    }
+
+    /// Sets whether the key with hex value `key` is pressed. Values above 0xF are ignored.
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        if let Some(key) = Key::from_hex(key) {
+            self.interpreter.set_key(key, pressed);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use chippy8::{assemble, decode, disassemble, AsmError, Instruction};
+
+#[test]
+fn assembles_labels_and_data() {
+    let source = "
+        ; Draw a square and loop forever
+        start:
+            CLS
+            LD I, square
+            LD V0, 10     ; x
+            LD V1, 0x0A   ; y
+            DRW V0, V1, 2
+        loop: JP loop
+        square:
+            DB 0b11000000, 0xC0
+    ";
+    let rom = assemble(source).unwrap();
+    assert_eq!(
+        rom,
+        [0x00, 0xE0, 0xA2, 0x0C, 0x60, 0x0A, 0x61, 0x0A, 0xD0, 0x12, 0x12, 0x0A, 0xC0, 0xC0]
+    );
+}
+
+#[test]
+fn assembles_disassembler_output() {
+    let rom = [
+        0x6A, 0x02, 0x8A, 0xB4, 0xF5, 0x65, 0xFC, 0x55, 0xB3, 0x00, 0xEA, 0xA1, 0x00, 0xEE, 0xF0,
+        0x00, 0x12, 0x34, 0xFF, 0xFF, 0x42,
+    ];
+    let source: Vec<_> = disassemble(&rom)
+        .into_iter()
+        .map(|(_, line)| line)
+        .collect();
+    assert_eq!(source[7], "F0 00 12 34 -> LD I, LONG 0x1234");
+    assert_eq!(assemble(&source.join("\n")).unwrap(), rom);
+}
+
+#[test]
+fn reports_the_line_of_an_error() {
+    let error = assemble("CLS\nJP nowhere").unwrap_err();
+    assert_eq!(error.line, 2);
+    assert_eq!(
+        error,
+        AsmError {
+            line: 2,
+            message: "undefined label \"nowhere\"".to_string()
+        }
+    );
+
+    assert_eq!(assemble("LD V0, 256").unwrap_err().line, 1);
+    assert_eq!(assemble("LD V0").unwrap_err().line, 1);
+}
+
+#[test]
+fn decodes_opcodes_into_instructions() {
+    assert_eq!(decode(0x00E0), Some(Instruction::ClearScreen));
+    assert_eq!(decode(0x1234), Some(Instruction::Jump(0x234)));
+    assert_eq!(
+        decode(0x6A02),
+        Some(Instruction::SetReg { x: 0xA, kk: 0x02 })
+    );
+    assert_eq!(decode(0xD125), Some(Instruction::Draw { x: 1, y: 2, n: 5 }));
+    assert_eq!(decode(0xF301), Some(Instruction::SelectPlanes { n: 3 }));
+    assert_eq!(decode(0x8AB9), None);
+    assert_eq!(decode(0xFFFF), None);
+
+    assert_eq!(decode(0x8AB4).unwrap().pattern(), "8xy4");
+    assert!(decode(0xF000).unwrap().is_xochip());
+    assert!(!decode(0xF007).unwrap().is_xochip());
+}
@@ -0,0 +1,330 @@
+use chippy8::{Clock, Interpreter, Quirks, Timing, UnknownOpcodePolicy};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+struct FakeClock(Rc<Cell<Duration>>);
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.0.get()
+    }
+}
+
+#[test]
+fn delay_timer_follows_the_clock() {
+    // LD V0, 0x02; LD DT, V0; LD V1, DT; JP 0x204
+    let rom = [0x60, 0x02, 0xF0, 0x15, 0xF1, 0x07, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    // Less than one timer period has passed, so the timer doesn't move
+    time.set(Duration::from_millis(10));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[1], 2);
+
+    time.set(Duration::from_millis(20));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[1], 1);
+}
+
+#[test]
+fn timers_do_not_catch_up_on_time_spent_paused() {
+    // LD V0, 0x3C; LD DT, V0; JP 0x204
+    let rom = [0x60, 0x3C, 0xF0, 0x15, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    interpreter.set_paused(true);
+    time.set(Duration::from_secs(10));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    interpreter.set_paused(false);
+    interpreter.run_for(Duration::ZERO).unwrap();
+    assert_eq!(interpreter.delay_timer(), 0x3C);
+
+    time.set(Duration::from_secs(10) + Duration::from_millis(20));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    assert_eq!(interpreter.delay_timer(), 0x3B);
+}
+
+#[test]
+fn tick_timers_counts_down_once_per_call() {
+    // LD V0, 0x3C; LD DT, V0; JP 0x204
+    let rom = [0x60, 0x3C, 0xF0, 0x15, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    for remaining in (0..60).rev() {
+        interpreter.tick_timers();
+        assert_eq!(interpreter.delay_timer(), remaining);
+    }
+    interpreter.tick_timers();
+    assert_eq!(interpreter.delay_timer(), 0);
+}
+
+#[test]
+fn speed_multiplier_scales_instructions_and_timers() {
+    // LD V0, 0x10; LD DT, V0; LD V1, DT; JP 0x204
+    let rom = [0x60, 0x10, 0xF0, 0x15, 0xF1, 0x07, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    interpreter.set_clock_hz(500);
+    interpreter.set_speed_multiplier(2.0);
+
+    // 10 ms is 5 instructions at 500 Hz, and 10 at double speed
+    interpreter.run_for(Duration::from_millis(10)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 10);
+
+    // 20 ms is a bit more than two periods of the timers at 120 Hz
+    time.set(Duration::from_millis(20));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    assert_eq!(interpreter.pc(), 0x204);
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[1], 0x0E);
+}
+
+#[test]
+fn step_frame_runs_a_frame_of_instructions() {
+    // LD V0, 0x10; LD DT, V0; JP 0x204
+    let rom = [0x60, 0x10, 0xF0, 0x15, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.set_clock_hz(600);
+    interpreter.set_paused(true);
+
+    interpreter.step_frame().unwrap();
+    assert_eq!(interpreter.cycle_count(), 10);
+    assert!(interpreter.is_paused());
+}
+
+#[test]
+fn instructions_per_frame_sets_the_speed_of_a_frame() {
+    // JP 0x200
+    let mut interpreter = Interpreter::try_new(&[0x12, 0x00]).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    interpreter.set_instructions_per_frame(11);
+
+    interpreter.step_frame().unwrap();
+    assert_eq!(interpreter.cycle_count(), 11);
+
+    // 11 instructions per frame is about 660 Hz
+    time.set(Duration::from_secs(1));
+    interpreter.run_for(Duration::from_millis(100)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 11 + 65);
+}
+
+#[test]
+fn sound_handlers_follow_the_sound_timer() {
+    // LD V0, 0x02; LD ST, V0; JP 0x204
+    let rom = [0x60, 0x02, 0xF0, 0x18, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let events = Rc::new(Cell::new((0, 0)));
+
+    let start_events = events.clone();
+    interpreter.set_sound_start_handler(Some(Box::new(move || {
+        let (starts, stops) = start_events.get();
+        start_events.set((starts + 1, stops));
+    })));
+    let stop_events = events.clone();
+    interpreter.set_sound_stop_handler(Some(Box::new(move || {
+        let (starts, stops) = stop_events.get();
+        stop_events.set((starts, stops + 1));
+    })));
+
+    interpreter.step().unwrap();
+    assert_eq!(events.get(), (0, 0));
+    interpreter.step().unwrap();
+    assert_eq!(events.get(), (1, 0));
+    interpreter.tick_timers();
+    interpreter.step().unwrap();
+    assert_eq!(events.get(), (1, 0));
+    interpreter.tick_timers();
+    assert_eq!(events.get(), (1, 1));
+}
+
+#[test]
+fn display_wait_limits_drawing_to_once_per_frame() {
+    // DRW V0, V0, 1; JP 0x200
+    let rom = [0xD0, 0x01, 0x12, 0x00];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter
+        .set_quirks(Quirks::preset("chip8").unwrap())
+        .unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+
+    // Only the first draw runs until the timers tick
+    interpreter.run_for(Duration::from_millis(10)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 1);
+    interpreter.run_for(Duration::from_millis(10)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 1);
+
+    time.set(Duration::from_millis(17));
+    interpreter.run_for(Duration::from_millis(10)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 3);
+
+    let mut quirks = interpreter.quirks();
+    quirks.display_wait = false;
+    interpreter.set_quirks(quirks).unwrap();
+    time.set(Duration::from_millis(40));
+    interpreter.run_for(Duration::from_millis(10)).unwrap();
+    assert_eq!(interpreter.cycle_count(), 8);
+}
+
+#[test]
+fn jump_to_itself_halts() {
+    // LD V0, 0x01; JP 0x202
+    let rom = [0x60, 0x01, 0x12, 0x02];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let halts = Rc::new(Cell::new(0));
+    let handler_halts = halts.clone();
+    interpreter.set_halt_handler(Some(Box::new(move |address| {
+        assert_eq!(address, 0x202);
+        handler_halts.set(handler_halts.get() + 1);
+    })));
+
+    interpreter.step().unwrap();
+    assert!(!interpreter.is_halted());
+    interpreter.step().unwrap();
+    assert!(interpreter.is_halted());
+    interpreter.step().unwrap();
+    assert!(interpreter.is_halted());
+    assert_eq!(halts.get(), 1);
+
+    interpreter.reset();
+    assert!(!interpreter.is_halted());
+}
+
+#[test]
+fn skipped_opcodes_are_reported_to_the_handler() {
+    // An unknown opcode; LD V0, 0x01
+    let rom = [0xE0, 0x00, 0x60, 0x01];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.set_unknown_opcode_policy(UnknownOpcodePolicy::Skip);
+    let skipped = Rc::new(RefCell::new(Vec::new()));
+    let handler_skipped = skipped.clone();
+    interpreter.set_skipped_opcode_handler(Some(Box::new(move |opcode| {
+        handler_skipped.borrow_mut().push(opcode);
+    })));
+
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[0], 0x01);
+    assert_eq!(*skipped.borrow(), [0xE000]);
+}
+
+#[test]
+fn timers_keep_counting_while_waiting_for_a_key() {
+    // LD V0, 0x05; LD DT, V0; LD V1, K
+    let rom = [0x60, 0x05, 0xF0, 0x15, 0xF1, 0x0A];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    for frame in 1..=3 {
+        time.set(Duration::from_micros(16_667 * frame));
+        interpreter.run_for(Duration::from_micros(16_667)).unwrap();
+        assert_eq!(interpreter.pc(), 0x204);
+    }
+    assert_eq!(interpreter.delay_timer(), 2);
+
+    // Stepping whole frames counts down the same way
+    interpreter.step_frame().unwrap();
+    assert_eq!(interpreter.pc(), 0x204);
+    assert_eq!(interpreter.delay_timer(), 1);
+}
+
+#[test]
+fn frame_handler_is_called_once_per_frame() {
+    // LD V0, 0x03; LD DT, V0; JP 0x204
+    let rom = [0x60, 0x03, 0xF0, 0x15, 0x12, 0x04];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let handler_frames = frames.clone();
+    interpreter.set_frame_handler(Some(Box::new(move |frame| {
+        handler_frames.borrow_mut().push(frame)
+    })));
+
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    interpreter.tick_timers();
+    // Two frames have passed, so the handler catches up on both
+    time.set(Duration::from_millis(34));
+    interpreter.run_for(Duration::ZERO).unwrap();
+
+    let frames = frames.borrow();
+    assert_eq!(
+        frames.iter().map(|f| f.frame).collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert_eq!(frames[0].cycle_count, 2);
+    assert_eq!(frames[0].delay_timer, 2);
+    assert_eq!(frames[2].delay_timer, 0);
+}
+
+#[test]
+fn timers_can_be_driven_externally() {
+    // JP 0x200
+    let rom = [0x12, 0x00];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let time = Rc::new(Cell::new(Duration::ZERO));
+    interpreter.set_clock(Box::new(FakeClock(time.clone())));
+    interpreter.set_auto_tick_timers(false);
+    interpreter.set_delay_timer(10);
+    interpreter.set_sound_timer(5);
+    assert!(interpreter.is_sound_playing());
+
+    time.set(Duration::from_millis(100));
+    interpreter.run_for(Duration::from_millis(100)).unwrap();
+    assert_eq!(interpreter.delay_timer(), 10);
+    assert_eq!(interpreter.sound_timer(), 5);
+
+    interpreter.tick_timers();
+    assert_eq!(interpreter.delay_timer(), 9);
+    assert_eq!(interpreter.sound_timer(), 4);
+
+    // Turning automatic ticking back on doesn't catch up on the time that has passed
+    interpreter.set_auto_tick_timers(true);
+    time.set(Duration::from_millis(110));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    assert_eq!(interpreter.delay_timer(), 9);
+    time.set(Duration::from_millis(117));
+    interpreter.run_for(Duration::ZERO).unwrap();
+    assert_eq!(interpreter.delay_timer(), 8);
+}
+
+#[test]
+fn vip_timing_makes_draws_cost_more_than_other_instructions() {
+    // LD V0, 0x01; JP 0x200
+    let cheap = [0x60, 0x01, 0x12, 0x00];
+    // DRW V0, V0, 1; JP 0x200
+    let expensive = [0xD0, 0x01, 0x12, 0x00];
+
+    let cycles_in_a_frame = |rom: &[u8], timing| {
+        let mut interpreter = Interpreter::try_new(rom).unwrap();
+        interpreter.set_timing(timing);
+        interpreter.step_frame().unwrap();
+        interpreter.cycle_count()
+    };
+
+    assert_eq!(cycles_in_a_frame(&cheap, Timing::Uniform), 8);
+    // 6xkk and 1nnn take 132 µs together, Dxyn alone takes longer than a frame
+    assert_eq!(cycles_in_a_frame(&cheap, Timing::Vip), 244);
+    assert_eq!(cycles_in_a_frame(&expensive, Timing::Vip), 1);
+}
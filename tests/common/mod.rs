@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+
+use chippy8::{DisplayBuffer, Interpreter, Quirks};
+
+/// Runs `rom` headless for `steps` instructions with the given quirks.
+pub fn run_with_quirks(rom: &[u8], quirks: Quirks, steps: usize) -> Interpreter {
+    let mut interpreter = Interpreter::with_seed(rom, 0).unwrap();
+    interpreter.set_quirks(quirks).unwrap();
+    for _ in 0..steps {
+        interpreter.step().unwrap();
+    }
+    interpreter
+}
+
+/// Runs `rom` headless for `steps` instructions with the CHIP-8 quirks.
+pub fn run(rom: &[u8], steps: usize) -> Interpreter {
+    run_with_quirks(rom, Quirks::preset("chip8").unwrap(), steps)
+}
+
+/// Runs `rom` headless for `frames` 60 Hz frames with the CHIP-8 quirks.
+pub fn run_frames(rom: &[u8], frames: usize) -> Interpreter {
+    let mut interpreter = Interpreter::with_seed(rom, 0).unwrap();
+    interpreter
+        .set_quirks(Quirks::preset("chip8").unwrap())
+        .unwrap();
+    for _ in 0..frames {
+        interpreter.step_frame().unwrap();
+    }
+    interpreter
+}
+
+/// Renders pixel values from [`DisplayBuffer::all_pixel_values`] one row per line, with `.` for
+/// unlit pixels and the value, from 1 to 3, for lit ones.
+fn render_values(values: &[u8]) -> Vec<String> {
+    values
+        .chunks_exact(DisplayBuffer::WIDTH)
+        .map(|row| {
+            row.iter()
+                .map(|&value| match value {
+                    0 => '.',
+                    value => char::from_digit(value as u32, 10).unwrap(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Asserts that every row and plane of the display matches `golden`, a screen rendered like
+/// [`render_values`] with one row per line. Surrounding whitespace is ignored, so the golden
+/// image can be an indented string literal.
+///
+/// On a mismatch, panics with the actual and expected screens side by side, followed by a
+/// screen where the differing pixels are marked with `X`.
+pub fn assert_display_eq(interpreter: &Interpreter, golden: &str) {
+    let actual = render_values(&interpreter.display().all_pixel_values());
+    let expected: Vec<&str> = golden
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if actual == expected {
+        return;
+    }
+
+    let mut diff = format!("{:<64}   {:<64}   diff\n", "actual", "expected");
+    let blank = " ".repeat(DisplayBuffer::WIDTH);
+    for row in 0..actual.len().max(expected.len()) {
+        let actual = actual.get(row).map_or(blank.as_str(), String::as_str);
+        let expected = expected.get(row).copied().unwrap_or(&blank);
+        let marks: String = (0..DisplayBuffer::WIDTH)
+            .map(|x| match (actual.chars().nth(x), expected.chars().nth(x)) {
+                (a, e) if a == e => '.',
+                _ => 'X',
+            })
+            .collect();
+        diff += &format!("{:<64}   {:<64}   {}\n", actual, expected, marks);
+    }
+    panic!("the display doesn't match the golden image\n{}", diff);
+}
+
+/// Returns whether the pixel at (`x`, `y`) is on.
+pub fn pixel(interpreter: &Interpreter, x: usize, y: usize) -> bool {
+    interpreter.get_display_buffer()[y * DisplayBuffer::WIDTH + x]
+}
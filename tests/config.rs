@@ -0,0 +1,50 @@
+#![cfg(feature = "desktop")]
+
+use chippy8::Config;
+use std::fs;
+
+fn load(name: &str, text: &str) -> Config {
+    let path = std::env::temp_dir().join(format!("chippy8-{}-{}.toml", name, std::process::id()));
+    fs::write(&path, text).unwrap();
+    let config = Config::load(Some(&path)).unwrap();
+    fs::remove_file(path).unwrap();
+    config
+}
+
+#[test]
+fn game_sections_override_the_rest() {
+    let config = load(
+        "override",
+        "
+        clock = 700
+        mute = true
+        fade = true
+
+        [games.PONG]
+        clock = 500
+        mute = false
+        ",
+    );
+
+    let args = config.args_for_rom("roms/PONG.ch8", &[0x12, 0x00]);
+    assert_eq!(args, ["--clock", "500", "--fade"]);
+
+    let args = config.args_for_rom("roms/TETRIS.ch8", &[0x12, 0x00]);
+    assert_eq!(args, ["--clock", "700", "--fade", "--mute"]);
+}
+
+#[test]
+fn game_speed_replaces_both_clock_and_ipf() {
+    let config = load(
+        "speed",
+        "
+        ipf = 11
+
+        [games.PONG]
+        clock = 500
+        ",
+    );
+
+    let args = config.args_for_rom("PONG", &[0x12, 0x00]);
+    assert_eq!(args, ["--clock", "500"]);
+}
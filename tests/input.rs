@@ -0,0 +1,62 @@
+use chippy8::{Interpreter, Key};
+
+#[test]
+fn fx0a_waits_for_key_release() {
+    // LD V0, K; JP 0x202
+    let mut interpreter = Interpreter::try_new(&[0xF0, 0x0A, 0x12, 0x02]).unwrap();
+
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.pc(), 0x200);
+
+    // Pressing and holding the key doesn't complete the instruction
+    interpreter.set_key(Key::Num5, true);
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.pc(), 0x200);
+
+    interpreter.set_key(Key::Num5, false);
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.pc(), 0x202);
+    assert_eq!(interpreter.registers()[0], 0x5);
+}
+
+#[test]
+fn fx0a_prefers_most_recently_pressed_key() {
+    // LD V0, K; JP 0x202
+    let mut interpreter = Interpreter::try_new(&[0xF0, 0x0A, 0x12, 0x02]).unwrap();
+    interpreter.step().unwrap();
+
+    interpreter.set_key(Key::A, true);
+    interpreter.set_key(Key::Num3, true);
+    interpreter.set_key(Key::Num3, false);
+    interpreter.set_key(Key::A, false);
+    interpreter.step().unwrap();
+
+    assert_eq!(interpreter.pc(), 0x202);
+    assert_eq!(interpreter.registers()[0], 0x3);
+}
+
+#[test]
+fn press_for_releases_after_the_given_frames() {
+    // JP 0x200
+    let mut interpreter = Interpreter::try_new(&[0x12, 0x00]).unwrap();
+    interpreter.press_for(Key::Num5, 3);
+    interpreter.press_for(Key::A, 1);
+    assert!(interpreter.is_key_pressed(Key::Num5));
+    assert!(interpreter.is_key_pressed(Key::A));
+
+    interpreter.tick_timers();
+    assert!(interpreter.is_key_pressed(Key::Num5));
+    assert!(!interpreter.is_key_pressed(Key::A));
+
+    interpreter.tick_timers();
+    assert!(interpreter.is_key_pressed(Key::Num5));
+    interpreter.tick_timers();
+    assert!(!interpreter.is_key_pressed(Key::Num5));
+
+    // Setting the key by hand cancels the release
+    interpreter.press_for(Key::Num5, 1);
+    interpreter.set_key(Key::Num5, true);
+    interpreter.tick_timers();
+    assert!(interpreter.is_key_pressed(Key::Num5));
+}
@@ -0,0 +1,550 @@
+use chippy8::{
+    byte_swap, looks_byte_swapped, Chip8Error, DrawEvent, ExecuteError, Interpreter,
+    InvalidFontAddress, InvalidState, LoadRomError, MemoryOutOfBounds, Quirks, SelfModifyingWrite,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn reads_memory_registers_and_stack() {
+    // LD I, 0x123; CALL 0x206; JP 0x204; RET
+    let rom = [0xA1, 0x23, 0x22, 0x06, 0x12, 0x04, 0x00, 0xEE];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+
+    assert_eq!(interpreter.memory_slice(0x200..0x208), Ok(&rom[..]));
+    // The font starts at address 0x050
+    assert_eq!(
+        interpreter.memory_slice(0x50..0x55),
+        Ok(&[0xF0, 0x90, 0x90, 0x90, 0xF0][..])
+    );
+    assert_eq!(interpreter.reg_i(), 0x123);
+    assert_eq!(interpreter.pc(), 0x206);
+    assert_eq!(interpreter.stack_frames(), [0x204]);
+
+    interpreter.step().unwrap();
+    assert!(interpreter.stack_frames().is_empty());
+    assert_eq!(interpreter.cycle_count(), 3);
+
+    interpreter.reset();
+    assert_eq!(interpreter.cycle_count(), 0);
+}
+
+#[test]
+fn font_can_be_moved() {
+    // LD V0, 0x01; LD F, V0
+    let mut interpreter = Interpreter::try_new(&[0x60, 0x01, 0xF0, 0x29]).unwrap();
+    interpreter.set_font_address(0x000).unwrap();
+    interpreter.reset();
+
+    assert_eq!(
+        interpreter.memory_slice(0..5),
+        Ok(&[0xF0, 0x90, 0x90, 0x90, 0xF0][..])
+    );
+    assert!(interpreter
+        .memory_slice(0x50..0xA0)
+        .unwrap()
+        .iter()
+        .all(|&byte| byte == 0));
+
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.reg_i(), 5);
+
+    assert_eq!(
+        interpreter.set_font_address(0x1C0),
+        Err(InvalidFontAddress { address: 0x1C0 })
+    );
+    assert_eq!(interpreter.memory_slice(0..1), Ok(&[0xF0][..]));
+}
+
+#[test]
+fn xochip_has_64_kib_of_memory() {
+    // LD V0, 0xAB; LD I, LONG 0xF000; LD [I], V0
+    let rom = [0x60, 0xAB, 0xF0, 0x00, 0xF0, 0x00, 0xF0, 0x55];
+    // F000 is only used by XO-CHIP, so the quirks are detected
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    assert_eq!(interpreter.memory_size(), 0x10000);
+
+    interpreter
+        .set_quirks(Quirks::preset("chip8").unwrap())
+        .unwrap();
+    assert_eq!(interpreter.memory_size(), 0x1000);
+    interpreter
+        .set_quirks(Quirks::preset("xochip").unwrap())
+        .unwrap();
+    assert_eq!(interpreter.memory_size(), 0x10000);
+
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.memory_slice(0xF000..0xF001), Ok(&[0xAB][..]));
+
+    interpreter.reset();
+    assert_eq!(interpreter.memory_size(), 0x10000);
+}
+
+#[test]
+fn memory_size_is_kept_when_the_quirks_change() {
+    let mut interpreter = Interpreter::try_new(&[0x12, 0x00]).unwrap();
+    interpreter.set_memory_size(0x2000).unwrap();
+    interpreter
+        .set_quirks(Quirks::preset("xochip").unwrap())
+        .unwrap();
+    assert_eq!(interpreter.memory_size(), 0x2000);
+    interpreter.reset();
+    assert_eq!(interpreter.memory_size(), 0x2000);
+
+    assert_eq!(
+        interpreter.set_memory_size(0x201),
+        Err(LoadRomError::TooLarge { len: 2, max: 1 })
+    );
+    assert_eq!(interpreter.memory_size(), 0x2000);
+}
+
+#[test]
+fn states_only_restore_into_memory_of_the_same_size() {
+    // LD V0, 0xAB; LD I, LONG 0xF000
+    let rom = [0x60, 0xAB, 0xF0, 0x00, 0xF0, 0x00];
+    let mut xochip = Interpreter::try_new(&rom).unwrap();
+    xochip.step().unwrap();
+    let state = xochip.snapshot();
+
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter
+        .set_quirks(Quirks::preset("chip8").unwrap())
+        .unwrap();
+    assert_eq!(
+        interpreter.restore(&state),
+        Err(InvalidState::MemorySize {
+            size: 0x10000,
+            expected: 0x1000
+        })
+    );
+    assert_eq!(interpreter.registers()[0], 0);
+
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    assert_eq!(interpreter.restore(&state), Ok(()));
+    assert_eq!(interpreter.registers()[0], 0xAB);
+}
+
+#[test]
+fn quirks_are_only_detected_from_reachable_instructions() {
+    // LD I, sprite; DRW V0, V0, 2; JP 0x204; sprite: DB 0x50, 0x12 (5xy2 as data)
+    let rom = [0xA2, 0x06, 0xD0, 0x02, 0x12, 0x04, 0x50, 0x12];
+    assert_eq!(Quirks::detect(&rom), None);
+
+    // CALL 0x206; JP 0x202; PLANE 1; RET
+    let rom = [0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0xF1, 0x01, 0x00, 0xEE];
+    assert_eq!(Quirks::detect(&rom), Quirks::preset("xochip"));
+}
+
+#[test]
+fn xochip_i_wraps_at_64_kib() {
+    // LD I, LONG 0x1FFF; LD V0, 0x02; ADD I, V0
+    let rom = [0xF0, 0x00, 0x1F, 0xFF, 0x60, 0x02, 0xF0, 0x1E];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.reg_i(), 0x2001);
+}
+
+#[test]
+fn long_load_at_the_end_of_memory_wraps_around() {
+    // LD I, LONG 0xABCD in the last four bytes of memory
+    let rom = [0xF0, 0x00, 0xAB, 0xCD];
+    let mut interpreter = Interpreter::with_entry_point(&rom, 0xFFFC).unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.reg_i(), 0xABCD);
+    assert_eq!(interpreter.pc(), 0x000);
+}
+
+#[test]
+fn quirks_without_xochip_need_the_rom_to_fit_in_4_kib() {
+    let mut rom = vec![0xF0, 0x00, 0xF0, 0x00];
+    rom.resize(0x1000, 0x00);
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+
+    let result = interpreter.set_quirks(Quirks::preset("chip8").unwrap());
+    assert_eq!(
+        result,
+        Err(LoadRomError::TooLarge {
+            len: 0x1000,
+            max: 0xE00
+        })
+    );
+    assert!(interpreter.quirks().xochip_opcodes);
+    assert_eq!(interpreter.memory_size(), 0x10000);
+    interpreter.reset();
+}
+
+#[test]
+fn rom_must_fit_in_memory() {
+    assert_eq!(Interpreter::try_new(&[]).err(), Some(LoadRomError::Empty));
+    assert_eq!(
+        Interpreter::try_new(&[0; 0xE01]).err(),
+        Some(LoadRomError::TooLarge {
+            len: 0xE01,
+            max: 0xE00
+        })
+    );
+    assert!(Interpreter::try_new(&[0; 0xE00]).is_ok());
+}
+
+#[test]
+fn errors_convert_into_chip8_error() {
+    fn run(rom: &[u8]) -> Result<(), Chip8Error> {
+        let mut interpreter = Interpreter::try_new(rom)?;
+        interpreter.step()?;
+        Ok(())
+    }
+
+    assert!(matches!(
+        run(&[]),
+        Err(Chip8Error::LoadRom(LoadRomError::Empty))
+    ));
+    assert!(matches!(
+        run(&[0x00, 0xEE]),
+        Err(Chip8Error::StackUnderflow)
+    ));
+    assert!(matches!(
+        run(&[0xE0, 0x00]),
+        Err(Chip8Error::UnknownOpcode(0xE000))
+    ));
+    assert!(run(&[0x12, 0x00]).is_ok());
+    fn poke(address: u16) -> Result<u8, Chip8Error> {
+        let mut interpreter = Interpreter::try_new(&[0x12, 0x00]).unwrap();
+        interpreter.poke(address, 0xAB)?;
+        Ok(interpreter.peek(address)?)
+    }
+    assert_eq!(poke(0x300).unwrap(), 0xAB);
+    assert!(matches!(
+        poke(0x1000),
+        Err(Chip8Error::AddressOutOfRange(0x1000))
+    ));
+
+    let stack_overflow = || -> Result<(), Chip8Error> {
+        let mut interpreter = Interpreter::try_new(&[0x22, 0x00]).unwrap();
+        for _ in 0..17 {
+            interpreter.step()?;
+        }
+        Ok(())
+    };
+    assert!(matches!(stack_overflow(), Err(Chip8Error::StackOverflow)));
+
+    let assemble = || -> Result<Vec<u8>, Chip8Error> { Ok(chippy8::assemble("NOPE")?) };
+    assert!(matches!(assemble(), Err(Chip8Error::Asm(_))));
+    let font = || -> Result<(), Chip8Error> {
+        Interpreter::try_new(&[0x12, 0x00])
+            .unwrap()
+            .set_font(&[0; 4])?;
+        Ok(())
+    };
+    assert!(matches!(font(), Err(Chip8Error::InvalidFont(_))));
+    let color = || -> Result<[u8; 4], Chip8Error> { Ok(chippy8::parse_color("red")?) };
+    assert!(matches!(color(), Err(Chip8Error::ParseColor(_))));
+}
+
+#[test]
+fn byte_swapped_roms_are_detected() {
+    // CLS; LD V0, 0x01; LD I, 0x050; DRW V0, V0, 5; ADD V0, 0x01; JP 0x202
+    let rom = [
+        0x00, 0xE0, 0x60, 0x01, 0xA0, 0x50, 0xD0, 0x05, 0x70, 0x01, 0x12, 0x02,
+    ];
+    let swapped = byte_swap(&rom);
+    assert_eq!(swapped[..4], [0xE0, 0x00, 0x01, 0x60]);
+    assert_eq!(byte_swap(&swapped), rom);
+
+    assert!(!looks_byte_swapped(&rom));
+    assert!(looks_byte_swapped(&swapped));
+    assert!(!looks_byte_swapped(&[]));
+}
+
+#[test]
+fn rom_can_start_at_another_entry_point() {
+    // LD V0, 0x01; JP 0x600
+    let rom = [0x60, 0x01, 0x16, 0x00];
+    let mut interpreter = Interpreter::with_entry_point(&rom, 0x600).unwrap();
+    assert_eq!(interpreter.pc(), 0x600);
+    assert_eq!(interpreter.memory_slice(0x600..0x604).unwrap(), rom);
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[0], 0x01);
+
+    interpreter.reset();
+    assert_eq!(interpreter.pc(), 0x600);
+
+    assert_eq!(
+        Interpreter::with_entry_point(&rom, 0x100).err(),
+        Some(LoadRomError::InvalidEntryPoint(0x100))
+    );
+    assert_eq!(
+        Interpreter::with_entry_point(&rom, 0x1000).err(),
+        Some(LoadRomError::InvalidEntryPoint(0x1000))
+    );
+    assert_eq!(
+        Interpreter::with_entry_point(&rom, 0xFFE).err(),
+        Some(LoadRomError::TooLarge { len: 4, max: 2 })
+    );
+}
+
+#[test]
+fn load_replaces_the_rom() {
+    // LD V0, 0x01
+    let mut interpreter = Interpreter::try_new(&[0x60, 0x01]).unwrap();
+    interpreter
+        .set_quirks(Quirks::preset("schip").unwrap())
+        .unwrap();
+    interpreter.step().unwrap();
+
+    assert_eq!(interpreter.load(&[]), Err(LoadRomError::Empty));
+    // LD V1, 0x02
+    interpreter.load(&[0x61, 0x02]).unwrap();
+    assert_eq!(interpreter.pc(), 0x200);
+    assert_eq!(interpreter.registers()[0], 0);
+    assert_eq!(interpreter.quirks(), Quirks::preset("schip").unwrap());
+
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[1], 0x02);
+}
+
+#[test]
+fn step_over_runs_the_whole_subroutine() {
+    // CALL 0x206; LD V1, 0x02; JP 0x204; LD V0, 0x01; ADD V0, 0x01; RET
+    let rom = [
+        0x22, 0x06, 0x61, 0x02, 0x12, 0x04, 0x60, 0x01, 0x70, 0x01, 0x00, 0xEE,
+    ];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+
+    interpreter.step_over().unwrap();
+    assert_eq!(interpreter.pc(), 0x202);
+    assert_eq!(interpreter.registers()[0], 0x02);
+    assert_eq!(interpreter.cycle_count(), 4);
+
+    // Anything but a call is a single step
+    interpreter.step_over().unwrap();
+    assert_eq!(interpreter.pc(), 0x204);
+    assert_eq!(interpreter.cycle_count(), 5);
+
+    // Breakpoints inside the subroutine still stop it
+    interpreter.reset();
+    interpreter.add_breakpoint(0x208);
+    interpreter.step_over().unwrap();
+    assert_eq!(interpreter.pc(), 0x208);
+    assert_eq!(interpreter.hit_breakpoint(), Some(0x208));
+}
+
+#[test]
+fn stack_overflows_past_16_calls() {
+    // CALL 0x200
+    let mut interpreter = Interpreter::try_new(&[0x22, 0x00]).unwrap();
+    for _ in 0..16 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.stack_depth(), 16);
+
+    assert_eq!(interpreter.step(), Err(ExecuteError::StackOverflow));
+    assert_eq!(interpreter.stack_depth(), 16);
+    assert!(interpreter
+        .stack_frames()
+        .iter()
+        .all(|&frame| frame == 0x202));
+}
+
+#[test]
+fn reports_writes_to_code() {
+    // LD V0, 0x12; LD I, 0x200; LD [I], V0; LD I, 0x300; LD [I], V0
+    let rom = [0x60, 0x12, 0xA2, 0x00, 0xF0, 0x55, 0xA3, 0x00, 0xF0, 0x55];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    let handler_writes = writes.clone();
+    interpreter.set_self_modifying_write_handler(Some(Box::new(move |write| {
+        handler_writes.borrow_mut().push(write)
+    })));
+
+    for _ in 0..5 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(
+        *writes.borrow(),
+        [SelfModifyingWrite {
+            pc: 0x204,
+            target: 0x200,
+            value: 0x12
+        }]
+    );
+}
+
+#[test]
+fn reports_draws_and_clears() {
+    // LD V0, 0x3E; LD V1, 0x02; LD I, 0x050; DRW V0, V1, 5; DRW V0, V1, 5; CLS
+    let rom = [
+        0x60, 0x3E, 0x61, 0x02, 0xA0, 0x50, 0xD0, 0x15, 0xD0, 0x15, 0x00, 0xE0,
+    ];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let handler_events = events.clone();
+    interpreter.set_draw_handler(Some(Box::new(move |event| {
+        handler_events.borrow_mut().push(event)
+    })));
+
+    for _ in 0..6 {
+        interpreter.step().unwrap();
+    }
+    let sprite = |collision| DrawEvent::Sprite {
+        x: 0x3E,
+        y: 0x02,
+        height: 5,
+        collision,
+    };
+    assert_eq!(
+        *events.borrow(),
+        [sprite(false), sprite(true), DrawEvent::Clear]
+    );
+}
+
+#[test]
+fn poke_and_peek_are_bounds_checked() {
+    // LD I, 0x300; LD V0, [I]
+    let mut interpreter = Interpreter::try_new(&[0xA3, 0x00, 0xF0, 0x65]).unwrap();
+    interpreter.poke(0x300, 0x2A).unwrap();
+    assert_eq!(interpreter.peek(0x300), Ok(0x2A));
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[0], 0x2A);
+
+    assert_eq!(interpreter.peek(0xFFF), Ok(0));
+    assert_eq!(
+        interpreter.poke(0x1000, 0x01),
+        Err(MemoryOutOfBounds { address: 0x1000 })
+    );
+    assert_eq!(
+        interpreter.peek(0x1000),
+        Err(MemoryOutOfBounds { address: 0x1000 })
+    );
+}
+
+#[test]
+fn memory_slice_is_bounds_checked() {
+    let interpreter = Interpreter::try_new(&[0x12, 0x00]).unwrap();
+    assert_eq!(interpreter.memory_slice(0xFF0..0x1000).unwrap().len(), 16);
+    assert_eq!(
+        interpreter.memory_slice(0xFF0..0x1001),
+        Err(MemoryOutOfBounds { address: 0x1000 })
+    );
+}
+
+#[test]
+fn profiling_counts_opcode_patterns() {
+    // LD V0, 0x03; ADD V0, 0xFF; SE V0, 0x00; JP 0x202; JP 0x208
+    let rom = [0x60, 0x03, 0x70, 0xFF, 0x30, 0x00, 0x12, 0x02, 0x12, 0x08];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.step().unwrap();
+    interpreter.set_profiling_enabled(true);
+    for _ in 0..9 {
+        interpreter.step().unwrap();
+    }
+
+    assert_eq!(
+        interpreter.opcode_stats(),
+        [("1nnn", 3), ("3xkk", 3), ("7xkk", 3)]
+    );
+}
+
+#[test]
+fn running_off_the_end_of_memory_is_an_error() {
+    // JP 0xFFE, where memory is zero, i.e. SYS 0x000
+    let mut interpreter = Interpreter::try_new(&[0x1F, 0xFE]).unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.pc(), 0x1000);
+    assert_eq!(interpreter.step(), Err(ExecuteError::PcOutOfRange(0x1000)));
+    assert_eq!(interpreter.pc(), 0x1000);
+
+    // JP 0xFFF, which leaves half an instruction
+    let mut interpreter = Interpreter::try_new(&[0x1F, 0xFF]).unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.step(), Err(ExecuteError::PcOutOfRange(0xFFF)));
+}
+
+#[test]
+fn step_back_undoes_steps() {
+    // LD V0, 0x01; LD I, 0x300; LD [I], V0; ADD V0, 0x01
+    let rom = [0x60, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x70, 0x01];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.set_step_history(2);
+    for _ in 0..4 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(interpreter.registers()[0], 0x02);
+
+    assert!(interpreter.step_back());
+    assert_eq!(interpreter.registers()[0], 0x01);
+    assert_eq!(interpreter.pc(), 0x206);
+    assert_eq!(interpreter.cycle_count(), 3);
+
+    assert!(interpreter.step_back());
+    assert_eq!(interpreter.pc(), 0x204);
+    assert_eq!(interpreter.memory_slice(0x300..0x301).unwrap(), [0]);
+
+    // Only two steps were kept
+    assert!(!interpreter.step_back());
+    assert_eq!(interpreter.pc(), 0x204);
+}
+
+#[test]
+fn failed_steps_are_not_undone() {
+    // ADD V0, 0x01; then an unknown opcode
+    let rom = [0x70, 0x01, 0xFF, 0xFF];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.set_step_history(4);
+    interpreter.step().unwrap();
+    assert!(interpreter.step().is_err());
+
+    assert!(interpreter.step_back());
+    assert_eq!(interpreter.pc(), 0x200);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.cycle_count(), 0);
+    assert!(!interpreter.step_back());
+}
+
+#[test]
+fn rewind_scrubs_through_recent_frames() {
+    // ADD V0, 0x01; JP 0x200
+    let rom = [0x70, 0x01, 0x12, 0x00];
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    // Two instructions, one loop, per frame
+    interpreter.set_clock_hz(120);
+    interpreter.set_rewind_history(3);
+    assert!(!interpreter.rewind(1));
+    for _ in 0..5 {
+        interpreter.step_frame().unwrap();
+    }
+    assert_eq!(interpreter.registers()[0], 5);
+
+    assert!(interpreter.rewind(1));
+    assert!(interpreter.is_paused());
+    assert_eq!(interpreter.registers()[0], 4);
+    assert_eq!(interpreter.cycle_count(), 8);
+
+    // Only three frames were kept
+    assert!(interpreter.rewind(5));
+    assert_eq!(interpreter.registers()[0], 3);
+    assert!(!interpreter.rewind(1));
+
+    assert!(interpreter.fast_forward(1));
+    assert_eq!(interpreter.registers()[0], 4);
+    assert!(interpreter.fast_forward(5));
+    assert_eq!(interpreter.registers()[0], 5);
+    assert!(!interpreter.fast_forward(1));
+
+    // Stepping from a rewound frame starts a new timeline
+    interpreter.rewind(2);
+    interpreter.step_frame().unwrap();
+    assert_eq!(interpreter.registers()[0], 4);
+    assert!(!interpreter.fast_forward(1));
+    assert!(interpreter.rewind(1));
+    assert_eq!(interpreter.registers()[0], 3);
+}
@@ -0,0 +1,600 @@
+mod common;
+
+use chippy8::{
+    assemble, DisplayBuffer, DrawMode, ExecuteError, FadeBuffer, Interpreter, InvalidFontLength,
+    Quirks,
+};
+use common::{assert_display_eq, pixel, run, run_frames, run_with_quirks};
+
+#[test]
+fn add_sets_carry() {
+    // LD V0, 0xFF; LD V1, 0x01; ADD V0, V1
+    let interpreter = run(&[0x60, 0xFF, 0x61, 0x01, 0x80, 0x14], 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    // LD V0, 0x01; LD V1, 0x01; ADD V0, V1
+    let interpreter = run(&[0x60, 0x01, 0x61, 0x01, 0x80, 0x14], 3);
+    assert_eq!(interpreter.registers()[0], 0x02);
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn sub_and_subn_set_not_borrow() {
+    // LD V0, 0x05; LD V1, 0x03; SUB V0, V1
+    let interpreter = run(&[0x60, 0x05, 0x61, 0x03, 0x80, 0x15], 3);
+    assert_eq!(interpreter.registers()[0], 0x02);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    // LD V0, 0x03; LD V1, 0x05; SUB V0, V1
+    let interpreter = run(&[0x60, 0x03, 0x61, 0x05, 0x80, 0x15], 3);
+    assert_eq!(interpreter.registers()[0], 0xFE);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    // LD V0, 0x03; LD V1, 0x05; SUBN V0, V1
+    let interpreter = run(&[0x60, 0x03, 0x61, 0x05, 0x80, 0x17], 3);
+    assert_eq!(interpreter.registers()[0], 0x02);
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+#[test]
+fn subn_sets_borrow_and_equal_operands_do_not_borrow() {
+    // LD V0, 0x05; LD V1, 0x03; SUBN V0, V1
+    let interpreter = run(&[0x60, 0x05, 0x61, 0x03, 0x80, 0x17], 3);
+    assert_eq!(interpreter.registers()[0], 0xFE);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    // A result of exactly zero is not a borrow
+    // LD V0, 0x07; LD V1, 0x07; SUB V0, V1
+    let interpreter = run(&[0x60, 0x07, 0x61, 0x07, 0x80, 0x15], 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    // LD V0, 0x07; LD V1, 0x07; SUBN V0, V1
+    let interpreter = run(&[0x60, 0x07, 0x61, 0x07, 0x80, 0x17], 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+#[test]
+fn flag_overwrites_the_result_in_vf() {
+    // LD VF, 0xFF; LD V1, 0x01; ADD VF, V1
+    let interpreter = run(&[0x6F, 0xFF, 0x61, 0x01, 0x8F, 0x14], 3);
+    assert_eq!(interpreter.registers()[0xF], 1);
+    // LD VF, 0x01; LD V1, 0x01; ADD VF, V1
+    let interpreter = run(&[0x6F, 0x01, 0x61, 0x01, 0x8F, 0x14], 3);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    // LD VF, 0x05; LD V1, 0x03; SUB VF, V1
+    let interpreter = run(&[0x6F, 0x05, 0x61, 0x03, 0x8F, 0x15], 3);
+    assert_eq!(interpreter.registers()[0xF], 1);
+    // LD VF, 0x03; LD V1, 0x05; SUB VF, V1
+    let interpreter = run(&[0x6F, 0x03, 0x61, 0x05, 0x8F, 0x15], 3);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    // LD V1, 0x02; SHR VF, V1
+    let interpreter = run(&[0x61, 0x02, 0x8F, 0x16], 2);
+    assert_eq!(interpreter.registers()[0xF], 0);
+    // LD V1, 0x81; SHL VF, V1
+    let interpreter = run(&[0x61, 0x81, 0x8F, 0x1E], 2);
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+#[test]
+fn vf_as_an_operand_is_read_before_the_flag_is_written() {
+    // LD V0, 0x01; LD VF, 0xFF; ADD V0, VF
+    let interpreter = run(&[0x60, 0x01, 0x6F, 0xFF, 0x80, 0xF4], 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    // LD V0, 0x01; LD VF, 0x03; SUB V0, VF
+    let interpreter = run(&[0x60, 0x01, 0x6F, 0x03, 0x80, 0xF5], 3);
+    assert_eq!(interpreter.registers()[0], 0xFE);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    // LD VF, 0x03; SHR V0, VF
+    let interpreter = run(&[0x6F, 0x03, 0x80, 0xF6], 2);
+    assert_eq!(interpreter.registers()[0], 0x01);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    // LD VF, 0x40; SHL V0, VF
+    let interpreter = run(&[0x6F, 0x40, 0x80, 0xFE], 2);
+    assert_eq!(interpreter.registers()[0], 0x80);
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn shifts_follow_the_shift_quirk() {
+    // LD V0, 0x00; LD V1, 0x03; SHR V0, V1
+    let shr = [0x60, 0x00, 0x61, 0x03, 0x80, 0x16];
+    // LD V0, 0x81; LD V1, 0x00; SHL V0, V1
+    let shl = [0x60, 0x81, 0x61, 0x00, 0x80, 0x1E];
+
+    let interpreter = run(&shr, 3);
+    assert_eq!(interpreter.registers()[0], 0x01);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    let interpreter = run(&shl, 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    let schip = Quirks::preset("schip").unwrap();
+
+    let interpreter = run_with_quirks(&shr, schip, 3);
+    assert_eq!(interpreter.registers()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    let interpreter = run_with_quirks(&shl, schip, 3);
+    assert_eq!(interpreter.registers()[0], 0x02);
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+#[test]
+fn logic_resets_vf_unless_disabled() {
+    for (operation, result) in [(0x1, 0x0F), (0x2, 0x04), (0x3, 0x0B)] {
+        // LD V0, 0x0C; LD V1, 0x07; LD VF, 0x01; OR/AND/XOR V0, V1
+        let rom = [0x60, 0x0C, 0x61, 0x07, 0x6F, 0x01, 0x80, 0x10 | operation];
+
+        let interpreter = run(&rom, 4);
+        assert_eq!(interpreter.registers()[0], result);
+        assert_eq!(interpreter.registers()[0xF], 0);
+
+        let schip = Quirks::preset("schip").unwrap();
+        let interpreter = run_with_quirks(&rom, schip, 4);
+        assert_eq!(interpreter.registers()[0], result);
+        assert_eq!(interpreter.registers()[0xF], 1);
+    }
+}
+
+#[test]
+fn return_resumes_after_the_call() {
+    // CALL 0x208; LD V0, 0x01; JP 0x204; JP 0x206; RET
+    let rom = [0x22, 0x08, 0x60, 0x01, 0x12, 0x04, 0x12, 0x06, 0x00, 0xEE];
+
+    let interpreter = run(&rom, 1);
+    assert_eq!(interpreter.pc(), 0x208);
+    assert_eq!(interpreter.stack_frames(), [0x202]);
+
+    let interpreter = run(&rom, 2);
+    assert_eq!(interpreter.pc(), 0x202);
+    assert!(interpreter.stack_frames().is_empty());
+
+    // The marker right after the call runs next
+    let interpreter = run(&rom, 3);
+    assert_eq!(interpreter.registers()[0], 0x01);
+    assert_eq!(interpreter.pc(), 0x204);
+}
+
+#[test]
+fn drawing_twice_erases_and_reports_collision() {
+    // LD V0, 0x00; LD F, V0; DRW V0, V0, 5
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0xD0, 0x05];
+
+    let interpreter = run(&rom, 3);
+    // The top row of the "0" glyph is 0xF0, the second row 0x90
+    assert!((0..4).all(|x| pixel(&interpreter, x, 0)));
+    assert!(!pixel(&interpreter, 4, 0));
+    assert!(pixel(&interpreter, 0, 1));
+    assert!(!pixel(&interpreter, 1, 1));
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    let packed = interpreter.packed_display();
+    assert_eq!(packed[0], 0xF0);
+    assert_eq!(packed[8], 0x90);
+    assert_eq!(packed[32], 0xF0);
+    assert_eq!(packed.iter().filter(|&&byte| byte != 0).count(), 5);
+
+    let interpreter = run(&rom, 4);
+    assert!(interpreter.get_display_buffer().iter().all(|&on| !on));
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+/// The sixteen font glyphs in two rows of eight, 8 pixels apart.
+const FONT_GOLDEN: &str = "
+    1111......1.....1111....1111....1..1....1111....1111....1111....
+    1..1.....11........1.......1....1..1....1.......1..........1....
+    1..1......1.....1111....1111....1111....1111....1111......1.....
+    1..1......1.....1..........1.......1.......1....1..1.....1......
+    1111.....111....1111....1111.......1....1111....1111.....1......
+    ................................................................
+    ................................................................
+    ................................................................
+    1111....1111....1111....111.....1111....111.....1111....1111....
+    1..1....1..1....1..1....1..1....1.......1..1....1.......1.......
+    1111....1111....1111....111.....1.......1..1....1111....1111....
+    1..1.......1....1..1....1..1....1.......1..1....1.......1.......
+    1111....1111....1..1....111.....1111....111.....1111....1.......
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+    ................................................................
+";
+
+#[test]
+fn font_glyphs_match_the_golden_image() {
+    let rom = assemble(
+        "
+        draw: LD F, V2
+        DRW V0, V1, 5
+        ADD V0, 8
+        ADD V2, 1
+        SE V0, 64
+        JP draw
+        LD V0, 0
+        ADD V1, 8
+        SE V2, 16
+        JP draw
+        done: JP done
+        ",
+    )
+    .unwrap();
+
+    // Each draw waits for the next frame
+    let interpreter = run_frames(&rom, 20);
+    assert_display_eq(&interpreter, FONT_GOLDEN);
+}
+
+#[test]
+fn draw_modes_combine_sprites_differently() {
+    // LD V0, 0x08; LD F, V0; DRW V1, V1, 5; LD V0, 0x00; LD F, V0; DRW V1, V1, 5
+    let rom = [
+        0x60, 0x08, 0xF0, 0x29, 0xD1, 0x15, 0x60, 0x00, 0xF0, 0x29, 0xD1, 0x15,
+    ];
+    let draw = |mode| {
+        let mut interpreter = Interpreter::with_seed(&rom, 0).unwrap();
+        interpreter.set_draw_mode(mode);
+        for _ in 0..6 {
+            interpreter.step().unwrap();
+        }
+        interpreter
+    };
+
+    // The "8" and "0" glyphs only differ in the middle row, 0xF0 against 0x90
+    let middle_row = |interpreter: &Interpreter| interpreter.packed_display()[16];
+    let interpreter = draw(DrawMode::Xor);
+    assert_eq!(middle_row(&interpreter), 0x60);
+    assert_eq!(interpreter.packed_display()[0], 0x00);
+    assert_eq!(interpreter.registers()[0xF], 1);
+
+    let interpreter = draw(DrawMode::Or);
+    assert_eq!(middle_row(&interpreter), 0xF0);
+    assert_eq!(interpreter.packed_display()[0], 0xF0);
+    assert_eq!(interpreter.registers()[0xF], 0);
+
+    let interpreter = draw(DrawMode::Set);
+    assert_eq!(middle_row(&interpreter), 0x90);
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn display_renders_as_text() {
+    // LD V0, 0x00; LD F, V0; DRW V0, V0, 5
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05];
+    let interpreter = run(&rom, 3);
+
+    let ascii = interpreter.display().to_ascii_with('#', '.');
+    let lines: Vec<&str> = ascii.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert!(lines[0].starts_with("####."));
+    assert!(lines[1].starts_with("#..#."));
+    assert_eq!(lines[5], ".".repeat(64));
+
+    let blocks = interpreter.display().to_half_blocks();
+    assert_eq!(blocks.lines().count(), 16);
+    assert!(blocks.starts_with("█▀▀█ "));
+}
+
+#[test]
+fn display_version_counts_changes() {
+    // CLS; LD V0, 0x00; DRW V0, V0, 0; LD F, V0; DRW V0, V0, 5
+    let rom = [0x00, 0xE0, 0x60, 0x00, 0xD0, 0x00, 0xF0, 0x29, 0xD0, 0x05];
+
+    let versions: Vec<_> = (0..=5)
+        .map(|steps| run(&rom, steps).display_version())
+        .collect();
+    // Drawing an empty sprite doesn't change anything
+    assert_eq!(versions, [0, 1, 1, 1, 1, 2]);
+}
+
+#[test]
+fn custom_font_replaces_the_digits() {
+    // LD V0, 0x01; LD F, V0; DRW V0, V0, 5
+    let rom = [0x60, 0x01, 0xF0, 0x29, 0xD0, 0x05];
+    let mut interpreter = Interpreter::with_seed(&rom, 0).unwrap();
+
+    let mut font = [0; 80];
+    // A "1" made of a single column
+    font[5..10].copy_from_slice(&[0x80; 5]);
+    assert_eq!(
+        interpreter.set_font(&font[..79]),
+        Err(InvalidFontLength { len: 79 })
+    );
+    interpreter.set_font(&font).unwrap();
+    interpreter.reset();
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+
+    assert!((1..6).all(|y| pixel(&interpreter, 1, y)));
+    assert!(!pixel(&interpreter, 2, 1));
+    assert_eq!(interpreter.memory_slice(0x200..0x206).unwrap(), rom);
+}
+
+#[test]
+fn schip_collision_counts_rows() {
+    // LD V0, 0x00; LD F, V0; DRW V0, V0, 5; LD V1, 0x03; DRW V1, V0, 5
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x61, 0x03, 0xD1, 0x05];
+
+    // Shifted right by 3, only the left column of each row of the "0" overlaps
+    let interpreter = run_with_quirks(&rom, Quirks::preset("schip").unwrap(), 5);
+    assert_eq!(interpreter.registers()[0xF], 5);
+
+    // LD V1, 0x01 instead, where the middle rows of the "0" don't overlap
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x61, 0x01, 0xD1, 0x05];
+    let interpreter = run_with_quirks(&rom, Quirks::preset("schip").unwrap(), 5);
+    assert_eq!(interpreter.registers()[0xF], 2);
+
+    let interpreter = run(&rom, 5);
+    assert_eq!(interpreter.registers()[0xF], 1);
+}
+
+fn bcd(value: u8) -> [u8; 3] {
+    // LD V3, value; LD I, 0x300; LD B, V3; LD I, 0x300; LD V2, [I]
+    let rom = [0x63, value, 0xA3, 0x00, 0xF3, 0x33, 0xA3, 0x00, 0xF2, 0x65];
+    let interpreter = run(&rom, 5);
+    let registers = interpreter.registers();
+    [registers[0], registers[1], registers[2]]
+}
+
+#[test]
+fn bcd_writes_hundreds_tens_and_units() {
+    assert_eq!(bcd(0), [0, 0, 0]);
+    assert_eq!(bcd(9), [0, 0, 9]);
+    assert_eq!(bcd(100), [1, 0, 0]);
+    assert_eq!(bcd(255), [2, 5, 5]);
+
+    for value in 0..=255 {
+        let [hundreds, tens, units] = bcd(value);
+        assert_eq!(hundreds * 100 + tens * 10 + units, value);
+    }
+}
+
+#[test]
+fn bcd_past_the_end_of_memory_is_an_error() {
+    // LD I, 0xFFE; LD B, V0
+    let mut interpreter = run(&[0xAF, 0xFE, 0xF0, 0x33], 1);
+    assert_eq!(
+        interpreter.step(),
+        Err(ExecuteError::MemoryOutOfBounds(0x1000))
+    );
+    assert_eq!(interpreter.pc(), 0x202);
+}
+
+#[test]
+fn store_and_load_all_registers_at_the_top_of_memory() {
+    let mut source = String::new();
+    for x in 0..16 {
+        source += &format!("LD V{}, {}\n", x, x * 3 + 1);
+    }
+    source += "LD I, 0xFF0\nLD [I], V15\nLD I, 0xFF0\n";
+    for x in 0..16 {
+        source += &format!("LD V{}, 0\n", x);
+    }
+    source += "LD V15, [I]";
+    let rom = assemble(&source).unwrap();
+
+    let interpreter = run(&rom, 16 + 2);
+    let expected: Vec<u8> = (0..16).map(|x| x * 3 + 1).collect();
+    assert_eq!(interpreter.memory_slice(0xFF0..0x1000).unwrap(), expected);
+    assert_eq!(interpreter.reg_i(), 0x1000);
+
+    let interpreter = run(&rom, 16 + 2 + 1 + 16 + 1);
+    assert_eq!(interpreter.registers()[..], expected);
+}
+
+#[test]
+fn store_and_load_past_the_end_of_memory_is_an_error() {
+    // LD I, 0xFF1; LD [I], VF
+    let mut interpreter = run(&[0xAF, 0xF1, 0xFF, 0x55], 1);
+    assert_eq!(
+        interpreter.step(),
+        Err(ExecuteError::MemoryOutOfBounds(0x1000))
+    );
+    assert_eq!(interpreter.reg_i(), 0xFF1);
+
+    // LD I, 0xFF1; LD VF, [I]
+    let mut interpreter = run(&[0xAF, 0xF1, 0xFF, 0x65], 1);
+    assert_eq!(
+        interpreter.step(),
+        Err(ExecuteError::MemoryOutOfBounds(0x1000))
+    );
+    assert_eq!(interpreter.registers(), &[0; 16]);
+}
+
+#[test]
+fn strict_mode_rejects_unaligned_jumps() {
+    // JP 0x203
+    let mut interpreter = run(&[0x12, 0x03], 0);
+    interpreter.set_strict(true);
+    assert_eq!(interpreter.step(), Err(ExecuteError::UnalignedJump(0x203)));
+    assert_eq!(interpreter.pc(), 0x200);
+
+    interpreter.set_strict(false);
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.pc(), 0x203);
+}
+
+#[test]
+fn sprites_past_the_end_of_memory_wrap_unless_strict() {
+    // LD I, 0xFFF; DRW V0, V0, 2
+    let rom = [0xAF, 0xFF, 0xD0, 0x02];
+    let mut interpreter = run(&rom, 1);
+    interpreter.set_strict(true);
+    assert_eq!(
+        interpreter.step(),
+        Err(ExecuteError::MemoryOutOfBounds(0x1000))
+    );
+
+    // The second row comes from address 0, which is zero
+    interpreter.set_strict(false);
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[0xF], 0);
+}
+
+#[test]
+fn sprites_wrap_unless_clipped() {
+    // LD V0, 0x00; LD F, V0; LD V1, 0x1F; DRW V0, V1, 5
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0x61, 0x1F, 0xD0, 0x15];
+
+    let interpreter = run(&rom, 4);
+    assert!((0..4).all(|x| pixel(&interpreter, x, 31)));
+    assert!((0..32)
+        .filter(|&y| y != 31)
+        .all(|y| (0..64).all(|x| !pixel(&interpreter, x, y))));
+
+    let quirks = Quirks {
+        clip_sprites: false,
+        ..Quirks::default()
+    };
+    let interpreter = run_with_quirks(&rom, quirks, 4);
+    assert!((0..4).all(|x| pixel(&interpreter, x, 31)));
+    // The second row of the "0" wraps around to the top
+    assert!(pixel(&interpreter, 0, 0));
+    assert!(!pixel(&interpreter, 1, 0));
+    assert!(pixel(&interpreter, 3, 0));
+    assert!(pixel(&interpreter, 0, 3));
+}
+
+#[test]
+fn wrapped_pixels_collide() {
+    // LD V0, 0x00; LD F, V0; DRW V0, V0, 5; LD V1, 0x1F; DRW V0, V1, 1
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x61, 0x1F, 0xD0, 0x12];
+    let quirks = Quirks {
+        clip_sprites: false,
+        ..Quirks::default()
+    };
+
+    // The second row of the sprite at y=31 lands on the first row of the "0" at y=0
+    let interpreter = run_with_quirks(&rom, quirks, 5);
+    assert_eq!(interpreter.registers()[0xF], 1);
+    assert!(!pixel(&interpreter, 0, 0));
+}
+
+#[test]
+fn vip_hires_programs_get_a_64x64_display() {
+    // JP 0x260 into the patched interpreter, which is skipped
+    let mut rom = vec![0x12, 0x60];
+    rom.resize(0xC0, 0x00);
+    // SYS 0x230 (hires clear); LD V0, 0x00; LD V1, 0x3C; LD F, V0; DRW V0, V1, 5
+    rom.extend([0x02, 0x30, 0x60, 0x00, 0x61, 0x3C, 0xF0, 0x29, 0xD0, 0x15]);
+
+    let interpreter = run(&rom, 1);
+    assert_eq!(interpreter.pc(), 0x260);
+    assert_eq!(interpreter.display().height(), DisplayBuffer::HEIGHT);
+
+    let quirks = Quirks {
+        vip_hires: true,
+        ..Quirks::default()
+    };
+    let interpreter = run_with_quirks(&rom, quirks, 6);
+    let display = interpreter.display();
+    assert!(display.is_hires());
+    assert_eq!(display.pixels().len(), DisplayBuffer::WIDTH * 64);
+    // The "0" drawn at y=60 reaches the last row instead of wrapping or being clipped
+    assert!(display.pixels()[63 * DisplayBuffer::WIDTH]);
+    assert_eq!(display.to_ascii().lines().count(), 64);
+}
+
+#[test]
+fn xochip_planes_are_drawn_and_cleared_separately() {
+    let rom = assemble(
+        "
+        LD V0, 0
+        LD I, sprite
+        PLANE 2
+        DRW V0, V0, 1   ; second plane only
+        PLANE 3
+        LD V1, 4
+        DRW V1, V0, 1   ; both planes, one row each
+        PLANE 1
+        CLS             ; first plane only
+        loop: JP loop
+        sprite: DB 0x80, 0xC0
+        ",
+    )
+    .unwrap();
+
+    let interpreter = run_with_quirks(&rom, Quirks::preset("xochip").unwrap(), 7);
+    let values = interpreter.get_display_values();
+    assert_eq!(&values[..6], [2, 0, 0, 0, 3, 2]);
+
+    let interpreter = run_with_quirks(&rom, Quirks::preset("xochip").unwrap(), 9);
+    let values = interpreter.get_display_values();
+    assert_eq!(&values[..6], [2, 0, 0, 0, 2, 2]);
+    // The first plane is what plain CHIP-8 frontends see
+    assert!(interpreter.get_display_buffer().iter().all(|&lit| !lit));
+}
+
+#[test]
+fn xochip_long_load_and_register_ranges() {
+    let rom = assemble(
+        "
+        LD I, LONG data
+        LOAD V1, V3
+        LD I, LONG 0x300
+        SAVE V3, V1     ; stored in reverse order
+        LD I, LONG 0x300
+        LOAD V4, V6
+        SE V0, 0
+        LD I, LONG 0x123 ; skipped as a whole
+        loop: JP loop
+        data: DB 0x11, 0x22, 0x33
+        ",
+    )
+    .unwrap();
+
+    let interpreter = run_with_quirks(&rom, Quirks::preset("xochip").unwrap(), 8);
+    assert_eq!(
+        interpreter.registers()[1..7],
+        [0x11, 0x22, 0x33, 0x33, 0x22, 0x11]
+    );
+    assert_eq!(interpreter.reg_i(), 0x300);
+    assert_eq!(interpreter.pc(), 0x218);
+
+    // Without the quirk, F000 is an unknown opcode
+    let mut interpreter = run(&rom, 0);
+    assert_eq!(interpreter.step(), Err(ExecuteError::UnknownOpcode(0xF000)));
+}
+
+#[test]
+fn clearing_fades_unless_the_fade_is_cleared() {
+    // LD F, V0; DRW V0, V0, 5; CLS
+    let rom = [0xF0, 0x29, 0xD0, 0x05, 0x00, 0xE0];
+    let mut interpreter = run(&rom, 2);
+    let mut fade = FadeBuffer::new();
+    fade.update(interpreter.get_display_buffer());
+    assert_eq!(interpreter.display().clear_count(), 0);
+
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.display().clear_count(), 1);
+    fade.update(interpreter.get_display_buffer());
+    assert_eq!(fade.intensity()[0], 255 - 64);
+
+    fade.clear();
+    assert!(fade.intensity().iter().all(|&intensity| intensity == 0));
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "net")]
+
+use chippy8::{SpectatorClient, SpectatorServer};
+use std::{thread, time::Duration};
+
+#[test]
+fn spectators_receive_broadcast_frames() {
+    let mut server = SpectatorServer::bind("127.0.0.1:0").unwrap();
+    let client = SpectatorClient::connect(server.local_addr().unwrap()).unwrap();
+
+    let mut frame = [0; 256];
+    frame[0] = 0xF0;
+    frame[255] = 0x01;
+
+    // Keep broadcasting until the server has accepted the client and the frame arrived
+    let received = (0..100).find_map(|_| {
+        server.broadcast(&frame);
+        thread::sleep(Duration::from_millis(10));
+        client.take_frame()
+    });
+    assert_eq!(received, Some(frame));
+    assert_eq!(server.spectators(), 1);
+
+    drop(server);
+    thread::sleep(Duration::from_millis(100));
+    assert!(!client.is_connected());
+}
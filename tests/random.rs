@@ -0,0 +1,32 @@
+use chippy8::Interpreter;
+
+// RND V0, 0xFF; RND V1, 0xFF; RND V2, 0x0F; JP 0x206
+const ROM: [u8; 8] = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0x0F, 0x12, 0x06];
+
+fn run_seeded(seed: u64) -> [u8; 16] {
+    let mut interpreter = Interpreter::with_seed(&ROM, seed).unwrap();
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+    *interpreter.registers()
+}
+
+#[test]
+fn cxkk_is_reproducible_with_a_seed() {
+    let registers = run_seeded(42);
+    assert_eq!(registers, run_seeded(42));
+    assert_ne!(registers[..3], run_seeded(43)[..3]);
+
+    // The random byte is masked with kk
+    assert_eq!(registers[2] & 0xF0, 0);
+}
+
+#[test]
+fn set_seed_reseeds_like_with_seed() {
+    let mut interpreter = Interpreter::with_seed(&ROM, 7).unwrap();
+    interpreter.set_seed(42);
+    for _ in 0..3 {
+        interpreter.step().unwrap();
+    }
+    assert_eq!(*interpreter.registers(), run_seeded(42));
+}
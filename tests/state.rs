@@ -0,0 +1,45 @@
+#![cfg(feature = "serde")]
+
+use chippy8::Interpreter;
+
+/// The offset of the font address in a save file, after the length and bytes of 4 KiB of memory.
+const FONT_ADDRESS_OFFSET: usize = 8 + 0x1000;
+
+#[test]
+fn states_survive_a_save_and_load() {
+    // LD V0, 0x2A; CALL 0x206; JP 0x204; RET
+    let rom = [0x60, 0x2A, 0x22, 0x06, 0x12, 0x04, 0x00, 0xEE];
+    let mut saved = Interpreter::try_new(&rom).unwrap();
+    saved.step().unwrap();
+    saved.step().unwrap();
+    let mut save = Vec::new();
+    saved.save_to_writer(&mut save).unwrap();
+
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    interpreter.load_from_reader(save.as_slice()).unwrap();
+    assert_eq!(interpreter.registers()[0], 0x2A);
+    assert_eq!(interpreter.stack_frames(), [0x204]);
+    assert_eq!(interpreter.pc(), 0x206);
+}
+
+#[test]
+fn corrupted_saves_are_rejected() {
+    let rom = [0x60, 0x2A];
+    let mut save = Vec::new();
+    Interpreter::try_new(&rom)
+        .unwrap()
+        .save_to_writer(&mut save)
+        .unwrap();
+
+    // A font at 0x1F0 would run into the program area
+    save[FONT_ADDRESS_OFFSET..FONT_ADDRESS_OFFSET + 8].copy_from_slice(&0x1F0u64.to_le_bytes());
+    let mut interpreter = Interpreter::try_new(&rom).unwrap();
+    assert!(interpreter.load_from_reader(save.as_slice()).is_err());
+    interpreter.reset();
+    interpreter.step().unwrap();
+    assert_eq!(interpreter.registers()[0], 0x2A);
+
+    // Cut off in the middle of memory
+    save.truncate(0x100);
+    assert!(interpreter.load_from_reader(save.as_slice()).is_err());
+}